@@ -0,0 +1,159 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::time::Duration;
+use chrono::TimeDelta;
+use crossterm::event::KeyCode;
+
+use crate::collector::Registry;
+use crate::metrics::SystemMetrics;
+use crate::session::{Player, SessionWriter};
+
+/// Settings taken from the command line.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Time between two refreshes.
+    pub interval: Duration,
+    /// Span of history kept and shown on the X axis.
+    pub history: Duration,
+    pub color: bool,
+}
+
+pub struct App {
+    /// Live history; a replay reads its window straight from the player.
+    metrics_history: Vec<SystemMetrics>,
+    pub current_tab: usize,
+    pub registry: Registry,
+    pub device_info: String,
+    pub options: Options,
+    /// Session file every refresh is saved to, with `--record`.
+    pub recorder: Option<SessionWriter<BufWriter<File>>>,
+    /// Recorded session shown instead of live data, with `--replay`.
+    pub player: Option<Player>,
+}
+
+impl App {
+    pub fn new(registry: Registry, device_info: String, options: Options) -> App {
+        App {
+            metrics_history: Vec::new(),
+            current_tab: 0,
+            registry,
+            device_info,
+            options,
+            recorder: None,
+            player: None,
+        }
+    }
+
+    /// Runs every collector once and appends the result to the history. When
+    /// replaying, only advances the playback position.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        if let Some(player) = &mut self.player {
+            player.tick();
+            return Ok(());
+        }
+
+        let metrics = self.registry.collect();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(&metrics)?;
+        }
+        self.update(metrics);
+        Ok(())
+    }
+
+    fn update(&mut self, metrics: SystemMetrics) {
+        // Drop samples that have scrolled out of the history window. A window
+        // reaching back past what chrono can represent keeps everything.
+        if let Some(oldest) = TimeDelta::from_std(self.options.history).ok()
+            .and_then(|history| metrics.timestamp.checked_sub_signed(history))
+        {
+            let expired = self.metrics_history.iter().take_while(|old| old.timestamp < oldest).count();
+            self.metrics_history.drain(..expired);
+        }
+
+        self.metrics_history.push(metrics);
+    }
+
+    /// Snapshots within the history window, oldest first. When replaying,
+    /// these are the recorded frames up to the playback position.
+    pub fn metrics_history(&self) -> &[SystemMetrics] {
+        match &self.player {
+            Some(player) => player.window(self.options.history),
+            None => &self.metrics_history,
+        }
+    }
+
+    /// History of one series in display units, plus padded Y-axis bounds. X is
+    /// the age of each sample in seconds relative to the latest one, so it runs
+    /// from minus the history window up to 0. Intervals in which the series
+    /// wasn't reported have no point rather than a zero.
+    pub fn get_data_for_chart(&self, key: &str) -> (Vec<(f64, f64)>, (f64, f64)) {
+        let mut data = Vec::new();
+        let mut min_val = f64::MAX;
+        let mut max_val = f64::MIN;
+        let history = self.metrics_history();
+        let Some(latest) = history.last() else {
+            return (data, (0.0, 10.0));
+        };
+
+        for metrics in history {
+            let Some(sample) = metrics.get(key) else {
+                continue;
+            };
+            let value = sample.value / sample.series.unit.display_scale().0;
+            let age = (metrics.timestamp - latest.timestamp).num_milliseconds() as f64 / 1000.0;
+
+            data.push((age, value));
+            if value < min_val { min_val = value; }
+            if value > max_val { max_val = value; }
+        }
+
+        if data.is_empty() {
+            return (data, (0.0, 10.0));
+        }
+
+        // Add some padding to the Y-axis bounds
+        let padding = (max_val - min_val) * 0.1;
+        let y_bounds = if max_val == min_val {
+            (0.0, max_val + 10.0)
+        } else {
+            ((min_val - padding).max(0.0), max_val + padding)
+        };
+
+        (data, y_bounds)
+    }
+
+    /// Selects the tab whose collector name matches, ignoring case.
+    pub fn select_tab(&mut self, name: &str) -> bool {
+        match self.registry.collectors().iter().position(|collector| collector.name().eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                self.current_tab = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Offers a key press to the current tab first.
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        self.registry.handle_key(self.current_tab, key)
+    }
+
+    pub fn next_tab(&mut self) {
+        let count = self.registry.collectors().len();
+        if count == 0 {
+            return;
+        }
+        self.current_tab = (self.current_tab + 1) % count;
+    }
+
+    pub fn previous_tab(&mut self) {
+        if self.registry.collectors().is_empty() {
+            return;
+        }
+        if self.current_tab > 0 {
+            self.current_tab -= 1;
+        } else {
+            self.current_tab = self.registry.collectors().len() - 1;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use chrono::TimeDelta;
+use clap::{Parser, ValueEnum};
+
+/// Real-time performance monitor for Rockchip RK35xx boards.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Read /proc and /sys below this directory instead of /, e.g. a captured fixture tree
+    #[arg(long, value_name = "DIR", default_value = "/")]
+    pub sysroot: PathBuf,
+
+    /// Time between samples, e.g. 200ms, 1s or 1m
+    #[arg(long, short, value_name = "TIME", default_value = "200ms", value_parser = parse_interval)]
+    pub interval: Duration,
+
+    /// How much history the charts keep and show, e.g. 2m or 1h
+    #[arg(long, value_name = "TIME", default_value = "2m", value_parser = parse_interval)]
+    pub history: Duration,
+
+    /// Tab to open first, by name (case-insensitive), e.g. npu
+    #[arg(long, value_name = "NAME")]
+    pub tab: Option<String>,
+
+    /// Draw without colors. Also set by a non-empty NO_COLOR environment variable
+    #[arg(long, env = "NO_COLOR", hide_env = true)]
+    pub no_color: bool,
+
+    /// Also save every sample to this session file, for a later --replay
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Show a recorded session instead of live data
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["record", "headless"])]
+    pub replay: Option<PathBuf>,
+
+    /// Serve Prometheus metrics over HTTP instead of starting the TUI
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = "0.0.0.0:9101",
+        conflicts_with_all = ["headless", "replay", "record"],
+    )]
+    pub serve: Option<String>,
+
+    /// Log samples instead of starting the TUI, e.g. over SSH or under systemd
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Record format in headless mode
+    #[arg(long, value_enum, default_value_t = Format::Csv, requires = "headless")]
+    pub format: Format,
+
+    /// Write records to this file instead of stdout
+    #[arg(long, short, value_name = "FILE", requires = "headless")]
+    pub output: Option<PathBuf>,
+
+    /// Stop after this long, e.g. 90s, 30m or 12h (plain numbers are seconds)
+    #[arg(long, value_name = "TIME", value_parser = parse_duration, requires = "headless")]
+    pub duration: Option<Duration>,
+
+    /// Stop after writing this many records
+    #[arg(long, value_name = "N", requires = "headless")]
+    pub samples: Option<u64>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One column per series, with a header row
+    Csv,
+    /// One JSON object per line (NDJSON)
+    #[value(alias = "ndjson")]
+    Json,
+}
+
+/// Parses `<number>[ms|s|m|h]`.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let (number, scale) = if let Some(number) = text.strip_suffix("ms") {
+        (number, 0.001)
+    } else if let Some(number) = text.strip_suffix('s') {
+        (number, 1.0)
+    } else if let Some(number) = text.strip_suffix('m') {
+        (number, 60.0)
+    } else if let Some(number) = text.strip_suffix('h') {
+        (number, 3600.0)
+    } else {
+        (text, 1.0)
+    };
+    let value = match number.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => value,
+        _ => return Err(format!("invalid duration '{}', expected e.g. 500ms, 90s, 30m or 12h", text)),
+    };
+    // Timestamps are chrono times, so a duration has to fit a TimeDelta too
+    Duration::try_from_secs_f64(value * scale)
+        .ok()
+        .filter(|duration| TimeDelta::from_std(*duration).is_ok())
+        .ok_or_else(|| format!("duration '{}' is too long", text))
+}
+
+/// A duration that must not be zero.
+fn parse_interval(text: &str) -> Result<Duration, String> {
+    match parse_duration(text)? {
+        Duration::ZERO => Err("must be greater than zero".to_string()),
+        duration => Ok(duration),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(43200)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("1e20").is_err());
+        assert!(parse_duration("1e15h").is_err());
+        assert!(parse_interval("0ms").is_err());
+    }
+}
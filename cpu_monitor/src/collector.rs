@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::Local;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::{Sample, Series, SystemMetrics};
+use crate::sysfs::SysFs;
+
+/// A source of samples. Each registered collector gets its own tab, and its
+/// samples end up in every chart and export without further wiring.
+pub trait Collector {
+    /// Tab title, also recorded as the `source` of every emitted series.
+    fn name(&self) -> &str;
+
+    /// Appends this interval's readings. All file access goes through `fs`.
+    /// Rates and deltas need an earlier reading, so the first interval leaves
+    /// them out rather than reporting zero.
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>);
+
+    /// Rows of charts drawn on the tab. An empty layout gives one chart per metric.
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        Vec::new()
+    }
+
+    /// Series shown in the status bar. Empty shows every series of the collector.
+    fn summary(&self) -> Vec<SeriesFilter> {
+        Vec::new()
+    }
+
+    /// Interactive list drawn above the charts, for tabs such as the clock tree.
+    fn panel(&self) -> Option<Panel> {
+        None
+    }
+
+    /// Handles a key press while the tab is shown. Returns false to leave the
+    /// key to the app, e.g. for switching tabs.
+    fn handle_key(&mut self, _key: KeyCode) -> bool {
+        false
+    }
+}
+
+/// Rows of text with one selected, drawn as a scrolling list.
+#[derive(Debug, Clone)]
+pub struct Panel {
+    pub title: String,
+    /// Column headings, kept in view while the rows scroll.
+    pub header: String,
+    pub rows: Vec<String>,
+    pub selected: usize,
+}
+
+/// Selects series by metric name and, optionally, label values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesFilter {
+    metric: String,
+    labels: Vec<(String, String)>,
+}
+
+impl SeriesFilter {
+    pub fn metric(metric: &str) -> SeriesFilter {
+        SeriesFilter { metric: metric.to_string(), labels: Vec::new() }
+    }
+
+    pub fn label(mut self, key: &str, value: impl Into<String>) -> SeriesFilter {
+        self.labels.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn matches(&self, series: &Series) -> bool {
+        series.metric == self.metric
+            && self.labels.iter().all(|(key, value)| series.label_value(key) == Some(value.as_str()))
+    }
+}
+
+/// One chart of a tab: every series matching one of the filters becomes a line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChartSpec {
+    pub title: String,
+    pub series: Vec<SeriesFilter>,
+    /// Series drawn as horizontal lines at their latest value, e.g. trip points.
+    pub thresholds: Vec<SeriesFilter>,
+    /// Fixed Y-axis range in display units; the axis auto-scales without it.
+    pub y_bounds: Option<(f64, f64)>,
+    /// Draw the series as stacked areas, in filter order from the bottom up.
+    pub stacked: bool,
+    /// Series whose non-zero samples are marked along the bottom of the chart, e.g. throttling.
+    pub marks: Vec<SeriesFilter>,
+    /// Draw the latest value of each series as a bar labelled with its name,
+    /// e.g. a histogram, instead of lines over time.
+    pub bars: bool,
+}
+
+impl ChartSpec {
+    pub fn new(title: impl Into<String>) -> ChartSpec {
+        ChartSpec {
+            title: title.into(),
+            series: Vec::new(),
+            thresholds: Vec::new(),
+            y_bounds: None,
+            stacked: false,
+            marks: Vec::new(),
+            bars: false,
+        }
+    }
+
+    pub fn series(mut self, filter: SeriesFilter) -> ChartSpec {
+        self.series.push(filter);
+        self
+    }
+
+    pub fn threshold(mut self, filter: SeriesFilter) -> ChartSpec {
+        self.thresholds.push(filter);
+        self
+    }
+
+    pub fn y_bounds(mut self, min: f64, max: f64) -> ChartSpec {
+        self.y_bounds = Some((min, max));
+        self
+    }
+
+    pub fn stacked(mut self) -> ChartSpec {
+        self.stacked = true;
+        self
+    }
+
+    pub fn mark(mut self, filter: SeriesFilter) -> ChartSpec {
+        self.marks.push(filter);
+        self
+    }
+
+    pub fn bars(mut self) -> ChartSpec {
+        self.bars = true;
+        self
+    }
+
+    pub fn matches(&self, series: &Series) -> bool {
+        self.series.iter().any(|filter| filter.matches(series))
+    }
+
+    /// Index of the first filter matching the series, which orders stacked layers.
+    pub fn position(&self, series: &Series) -> Option<usize> {
+        self.series.iter().position(|filter| filter.matches(series))
+    }
+
+    pub fn matches_threshold(&self, series: &Series) -> bool {
+        self.thresholds.iter().any(|filter| filter.matches(series))
+    }
+
+    pub fn matches_mark(&self, series: &Series) -> bool {
+        self.marks.iter().any(|filter| filter.matches(series))
+    }
+}
+
+/// One chart per metric name, in the order the collector emitted them.
+fn default_layout(source: &str, latest: &SystemMetrics) -> Vec<Vec<ChartSpec>> {
+    let mut layout: Vec<Vec<ChartSpec>> = Vec::new();
+    for sample in latest.samples.iter().filter(|sample| sample.series.source == source) {
+        let series = &sample.series;
+        if layout.iter().flatten().any(|chart| chart.matches(series)) {
+            continue;
+        }
+        let (_, suffix) = series.unit.display_scale();
+        let title = if suffix.is_empty() { series.name.clone() } else { format!("{} ({})", series.name, suffix) };
+        layout.push(vec![ChartSpec::new(title).series(SeriesFilter::metric(&series.metric))]);
+    }
+    layout
+}
+
+/// Refreshes a reading shared by several collectors.
+type SharedRefresh = Box<dyn FnMut(&dyn SysFs)>;
+
+/// Owns the collectors and runs them once per interval.
+pub struct Registry {
+    fs: Box<dyn SysFs>,
+    collectors: Vec<Box<dyn Collector>>,
+    /// Series reported in the last interval, so the history shares one
+    /// allocation per series.
+    series: HashMap<String, Arc<Series>>,
+    /// Readings shared by several collectors, such as the clock tree,
+    /// refreshed once at the start of every collect.
+    shared: Vec<SharedRefresh>,
+}
+
+impl Registry {
+    pub fn new(fs: Box<dyn SysFs>) -> Registry {
+        Registry { fs, collectors: Vec::new(), series: HashMap::new(), shared: Vec::new() }
+    }
+
+    /// Runs `refresh` at the start of every collect, before any collector.
+    pub fn before_collect(&mut self, refresh: impl FnMut(&dyn SysFs) + 'static) {
+        self.shared.push(Box::new(refresh));
+    }
+
+    pub fn register(&mut self, collector: impl Collector + 'static) {
+        self.collectors.push(Box::new(collector));
+    }
+
+    pub fn collectors(&self) -> &[Box<dyn Collector>] {
+        &self.collectors
+    }
+
+    pub fn collect(&mut self) -> SystemMetrics {
+        let timestamp = Local::now();
+        for refresh in &mut self.shared {
+            refresh(&*self.fs);
+        }
+
+        let mut samples = Vec::new();
+        for collector in &mut self.collectors {
+            let start = samples.len();
+            collector.collect(&*self.fs, &mut samples);
+            for sample in &mut samples[start..] {
+                sample.series = intern(&mut self.series, collector.name(), &sample.series);
+            }
+        }
+        let metrics = SystemMetrics::new(timestamp, samples);
+        // Series such as the busiest processes come and go, so only keep the ones
+        // still reported. The history holds its own references to the rest.
+        self.series.retain(|key, _| metrics.get(key).is_some());
+        metrics
+    }
+
+    /// Layout of the collector's tab, falling back to one chart per metric.
+    pub fn layout(&self, index: usize, latest: &SystemMetrics) -> Vec<Vec<ChartSpec>> {
+        let collector = &self.collectors[index];
+        let layout = collector.layout();
+        if layout.is_empty() {
+            default_layout(collector.name(), latest)
+        } else {
+            layout
+        }
+    }
+
+    pub fn panel(&self, index: usize) -> Option<Panel> {
+        self.collectors[index].panel()
+    }
+
+    pub fn handle_key(&mut self, index: usize, key: KeyCode) -> bool {
+        self.collectors[index].handle_key(key)
+    }
+
+    /// Samples of the collector selected for the status bar.
+    pub fn summary<'a>(&self, index: usize, latest: &'a SystemMetrics) -> Vec<&'a Sample> {
+        let collector = &self.collectors[index];
+        let filters = collector.summary();
+        latest.samples.iter()
+            .filter(|sample| sample.series.source == collector.name())
+            .filter(|sample| filters.is_empty() || filters.iter().any(|filter| filter.matches(&sample.series)))
+            .collect()
+    }
+}
+
+fn intern(cache: &mut HashMap<String, Arc<Series>>, source: &str, series: &Arc<Series>) -> Arc<Series> {
+    if let Some(cached) = cache.get(series.key())
+        && cached.source == source
+        && cached.name == series.name
+        && cached.unit == series.unit
+        && cached.kind == series.kind
+        && cached.top_n == series.top_n
+    {
+        return cached.clone();
+    }
+
+    let mut owned = Series::clone(series);
+    owned.source = source.to_string();
+    let owned = Arc::new(owned);
+    cache.insert(series.key().to_string(), owned.clone());
+    owned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Unit;
+    use crate::sysfs::fixture;
+
+    /// Reports a different pid every interval, like the busiest processes.
+    struct Rotating(u32);
+
+    impl Collector for Rotating {
+        fn name(&self) -> &str {
+            "Rotating"
+        }
+
+        fn collect(&mut self, _fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+            self.0 += 1;
+            samples.push(Sample::new(Series::new("process_cpu", Unit::Percent).label("pid", self.0), 1.0));
+        }
+    }
+
+    #[test]
+    fn forgets_series_that_are_gone() {
+        let mut registry = Registry::new(Box::new(fixture("rk3588")));
+        registry.register(Rotating(0));
+        let first = registry.collect();
+        for _ in 0..10 {
+            registry.collect();
+        }
+        assert_eq!(registry.series.len(), 1);
+        assert!(registry.series.contains_key(r#"process_cpu{pid="11"}"#));
+        assert_eq!(first.samples[0].series.source, "Rotating");
+    }
+}
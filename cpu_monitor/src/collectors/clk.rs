@@ -0,0 +1,318 @@
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use crossterm::event::KeyCode;
+
+use crate::collector::{ChartSpec, Collector, Panel, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+pub const CLK_SUMMARY: &str = "/sys/kernel/debug/clk/clk_summary";
+
+/// One row of clk_summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    pub name: String,
+    /// Depth in the tree, 0 for root clocks such as `xin24m`.
+    pub depth: usize,
+    /// Index of the parent clock in the parsed list.
+    pub parent: Option<usize>,
+    pub enable_count: u32,
+    pub prepare_count: u32,
+    /// Rate in Hz.
+    pub rate: u64,
+}
+
+/// Parses clk_summary into a flat list in file order, which is depth-first.
+/// Rows are `name enable_count prepare_count protect_count rate ...`, with the
+/// name indented three spaces per level below its parent.
+pub fn parse_clk_summary(content: &str) -> Vec<Clock> {
+    let mut clocks: Vec<Clock> = Vec::new();
+    // Indentation and index of the clocks on the path to the current row
+    let mut path: Vec<(usize, usize)> = Vec::new();
+    for line in content.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 {
+            continue;
+        }
+        // The two header rows and the dashed rule don't have a numeric rate, and
+        // neither do the consumer rows newer kernels print below each clock
+        let (Ok(enable_count), Ok(prepare_count), Ok(rate)) =
+            (parts[1].parse::<u32>(), parts[2].parse::<u32>(), parts[4].parse::<u64>())
+        else {
+            continue;
+        };
+
+        let indent = line.len() - line.trim_start().len();
+        while path.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+            path.pop();
+        }
+        clocks.push(Clock {
+            name: parts[0].to_string(),
+            depth: path.len(),
+            parent: path.last().map(|(_, parent)| *parent),
+            enable_count,
+            prepare_count,
+            rate,
+        });
+        path.push((indent, clocks.len() - 1));
+    }
+    clocks
+}
+
+/// clk_summary parsed once per interval and shared by every collector that
+/// needs clock rates. On RK3588 the file runs to several hundred lines, and
+/// each read takes the kernel's clk prepare lock.
+#[derive(Debug, Clone, Default)]
+pub struct ClockTree(Rc<RefCell<Vec<Clock>>>);
+
+impl ClockTree {
+    /// Reads the tree once; it stays empty without debugfs.
+    pub fn new(fs: &dyn SysFs) -> ClockTree {
+        let tree = ClockTree::default();
+        tree.refresh(fs);
+        tree
+    }
+
+    /// Re-reads clk_summary. The registry does this at the start of every collect.
+    pub fn refresh(&self, fs: &dyn SysFs) {
+        *self.0.borrow_mut() = fs.read_to_string(CLK_SUMMARY).map(|content| parse_clk_summary(&content)).unwrap_or_default();
+    }
+
+    pub fn clocks(&self) -> Ref<'_, Vec<Clock>> {
+        self.0.borrow()
+    }
+
+    /// Rate in Hz of the named clock, or 0 if it is gone.
+    pub fn rate(&self, name: &str) -> u64 {
+        self.0.borrow().iter().find(|clock| clock.name == name).map_or(0, |clock| clock.rate)
+    }
+}
+
+/// Formats a rate like the rest of the UI, in MHz, keeping kHz precision.
+fn format_rate(rate: u64) -> String {
+    format!("{:.3} MHz", rate as f64 / 1_000_000.0)
+}
+
+/// The whole clock tree as a browsable list. Pinned clocks are sampled every
+/// interval and charted below the tree.
+pub struct ClockCollector {
+    tree: ClockTree,
+    pinned: Vec<String>,
+    /// Selected row of the visible list.
+    selected: usize,
+    query: String,
+    /// Typing goes into the query until Enter or Esc.
+    searching: bool,
+    /// Hide clocks whose enable count is zero.
+    enabled_only: bool,
+}
+
+impl ClockCollector {
+    pub fn discover(tree: &ClockTree) -> Option<ClockCollector> {
+        if tree.clocks().is_empty() {
+            return None;
+        }
+        Some(ClockCollector {
+            tree: tree.clone(),
+            pinned: Vec::new(),
+            selected: 0,
+            query: String::new(),
+            searching: false,
+            enabled_only: false,
+        })
+    }
+
+    /// Indices of the clocks shown with the current search and filter. A match
+    /// keeps its ancestors so the tree stays readable.
+    fn visible(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        let clocks = self.tree.clocks();
+        let mut shown = vec![false; clocks.len()];
+        for (index, clock) in clocks.iter().enumerate() {
+            if self.enabled_only && clock.enable_count == 0 {
+                continue;
+            }
+            if !clock.name.to_lowercase().contains(&query) {
+                continue;
+            }
+            let mut next = Some(index);
+            while let Some(current) = next
+                && !shown[current]
+            {
+                shown[current] = true;
+                next = clocks[current].parent;
+            }
+        }
+        (0..clocks.len()).filter(|index| shown[*index]).collect()
+    }
+
+    fn toggle_pin(&mut self) {
+        let Some(name) = self.visible().get(self.selected).map(|index| self.tree.clocks()[*index].name.clone()) else {
+            return;
+        };
+        match self.pinned.iter().position(|pinned| *pinned == name) {
+            Some(position) => {
+                self.pinned.remove(position);
+            }
+            None => self.pinned.push(name),
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+    }
+}
+
+impl Collector for ClockCollector {
+    fn name(&self) -> &str {
+        "Clocks"
+    }
+
+    fn collect(&mut self, _fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        // The registry has already refreshed the shared tree for this interval
+        self.clamp_selection();
+
+        let clocks = self.tree.clocks();
+        for name in &self.pinned {
+            let Some(clock) = clocks.iter().find(|clock| clock.name == *name) else {
+                continue;
+            };
+            let series = |metric, unit| Series::new(metric, unit).label("clock", name).name(name);
+            samples.push(Sample::new(series("clock_rate", Unit::Hertz), clock.rate as f64));
+            samples.push(Sample::new(series("clock_enable_count", Unit::Count), clock.enable_count as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        // Filters cover every pinned clock, so the layout doesn't change when pinning
+        vec![vec![
+            ChartSpec::new("Pinned Clock Rates (MHz)").series(SeriesFilter::metric("clock_rate")),
+            ChartSpec::new("Pinned Clock Enable Counts").series(SeriesFilter::metric("clock_enable_count")),
+        ]]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("clock_rate")]
+    }
+
+    fn panel(&self) -> Option<Panel> {
+        let visible = self.visible();
+        let clocks = self.tree.clocks();
+        let width = visible.iter()
+            .map(|index| clocks[*index].depth * 2 + clocks[*index].name.len())
+            .max()
+            .unwrap_or(0)
+            .max("clock".len());
+
+        let header = format!("  {:<width$} {:>6} {:>7} {:>16}  parent", "clock", "enable", "prepare", "rate");
+        let rows = visible.iter().map(|index| {
+            let clock = &clocks[*index];
+            let pin = if self.pinned.contains(&clock.name) { '*' } else { ' ' };
+            let name = format!("{}{}", "  ".repeat(clock.depth), clock.name);
+            let parent = clock.parent.map_or("-", |parent| clocks[parent].name.as_str());
+            format!(
+                "{} {:<width$} {:>6} {:>7} {:>16}  {}",
+                pin, name, clock.enable_count, clock.prepare_count, format_rate(clock.rate), parent,
+            )
+        }).collect();
+
+        let mut title = format!("Clock Tree ({} of {})", visible.len(), clocks.len());
+        if self.searching || !self.query.is_empty() {
+            title.push_str(&format!(" - search: {}{}", self.query, if self.searching { "_" } else { "" }));
+        }
+        if self.enabled_only {
+            title.push_str(" - enabled only");
+        }
+        title.push_str(" - ↑/↓ select, / search, e enabled, p pin");
+        Some(Panel { title, header, rows, selected: self.selected })
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        if self.searching {
+            match key {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                _ => return false,
+            }
+            self.selected = 0;
+            return true;
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected += 1,
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(20),
+            KeyCode::PageDown => self.selected += 20,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Char('e') => {
+                self.enabled_only = !self.enabled_only;
+                self.selected = 0;
+            }
+            KeyCode::Char('p') | KeyCode::Enter => self.toggle_pin(),
+            _ => return false,
+        }
+        self.clamp_selection();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+
+    #[test]
+    fn parses_the_tree() {
+        let content = fixture("rk3588").read_to_string(CLK_SUMMARY).unwrap();
+        let clocks = parse_clk_summary(&content);
+        let clock = |name: &str| clocks.iter().find(|clock| clock.name == name).unwrap();
+
+        assert_eq!(clocks[0].name, "xin24m");
+        assert_eq!(clocks[0].parent, None);
+        let jpeg = clock("aclk_jpeg_decoder");
+        assert_eq!(jpeg.depth, 3);
+        assert_eq!(clocks[jpeg.parent.unwrap()].name, "aclk_vdpu_root");
+        assert_eq!((jpeg.enable_count, jpeg.prepare_count, jpeg.rate), (1, 1, 594_000_000));
+        // A sibling after a deeper subtree goes back to its own parent
+        assert_eq!(clocks[clock("aclk_rga2").parent.unwrap()].name, "gpll");
+    }
+
+    #[test]
+    fn searches_and_pins() {
+        let fs = fixture("rk3588");
+        let mut collector = ClockCollector::discover(&ClockTree::new(&fs)).unwrap();
+        collector.handle_key(KeyCode::Char('/'));
+        for c in "jpeg".chars() {
+            collector.handle_key(KeyCode::Char(c));
+        }
+        collector.handle_key(KeyCode::Enter);
+        let clocks = collector.tree.clocks().clone();
+        let names: Vec<_> = collector.visible().iter().map(|index| clocks[*index].name.as_str()).collect();
+        assert_eq!(names, ["xin24m", "cpll", "aclk_vdpu_root", "aclk_jpeg_decoder", "aclk_jpeg_encoder0"]);
+
+        collector.handle_key(KeyCode::Char('e'));
+        assert_eq!(collector.visible().len(), 4);
+        for _ in 0..3 {
+            collector.handle_key(KeyCode::Down);
+        }
+        collector.handle_key(KeyCode::Char('p'));
+        assert_eq!(collector.pinned, ["aclk_jpeg_decoder"]);
+
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        let keys: Vec<_> = samples.iter().map(|sample| (sample.series.key(), sample.value)).collect();
+        assert_eq!(keys, [
+            (r#"clock_rate{clock="aclk_jpeg_decoder"}"#, 594_000_000.0),
+            (r#"clock_enable_count{clock="aclk_jpeg_decoder"}"#, 1.0),
+        ]);
+        // Keys the tab doesn't use go on to the app, so 'q' still quits
+        assert!(!collector.handle_key(KeyCode::Char('q')));
+    }
+}
@@ -0,0 +1,393 @@
+use std::time::Instant;
+use crossterm::event::KeyCode;
+
+use crate::collector::{ChartSpec, Collector, Panel, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::process::{ProcessCollector, REFRESH_INTERVAL};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Cgroups charted every interval when none is opened, busiest first.
+const TOP_CGROUPS: usize = 5;
+
+/// One cgroup v2 directory below the root.
+#[derive(Debug, Clone, PartialEq)]
+struct Cgroup {
+    /// Path below the root, e.g. `system.slice/ssh.service`.
+    path: String,
+    depth: usize,
+    /// Whether other cgroups are nested in this one.
+    has_children: bool,
+    /// `usage_usec` from `cpu.stat`.
+    cpu_usage: u64,
+    /// `memory.current` in bytes.
+    memory: u64,
+    /// `memory.max` in bytes, `None` when unlimited.
+    memory_max: Option<u64>,
+    /// Bytes read and written over all devices in `io.stat`.
+    io_read: u64,
+    io_write: u64,
+    /// `pids.current`.
+    pids: u64,
+    /// Share of one core over the last interval, in percent.
+    cpu: f64,
+    /// I/O over the last interval in bytes per second.
+    read_rate: f64,
+    write_rate: f64,
+}
+
+impl Cgroup {
+    fn read(fs: &dyn SysFs, path: &str, depth: usize) -> Cgroup {
+        let file = |name: &str| fs.read_to_string(&format!("{}/{}/{}", CGROUP_ROOT, path, name)).unwrap_or_default();
+        let number = |name: &str| file(name).trim().parse::<u64>().ok();
+
+        let cpu_usage = file("cpu.stat").lines()
+            .find_map(|line| line.strip_prefix("usage_usec "))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0);
+        // Rows are `<major>:<minor> rbytes=... wbytes=... rios=... wios=...`, one per device
+        let io = file("io.stat");
+        let io_total = |key: &str| io.split_whitespace()
+            .filter_map(|field| field.strip_prefix(key)?.parse::<u64>().ok())
+            .sum::<u64>();
+
+        Cgroup {
+            path: path.to_string(),
+            depth,
+            has_children: false,
+            cpu_usage,
+            memory: number("memory.current").unwrap_or(0),
+            memory_max: number("memory.max"),
+            io_read: io_total("rbytes="),
+            io_write: io_total("wbytes="),
+            pids: number("pids.current").unwrap_or(0),
+            cpu: 0.0,
+            read_rate: 0.0,
+            write_rate: 0.0,
+        }
+    }
+
+    fn name(&self) -> String {
+        display_name(self.path.rsplit('/').next().unwrap_or(&self.path))
+    }
+}
+
+/// Shortens container scopes such as `docker-<64 hex digits>.scope` to the
+/// 12-digit id `docker ps` shows.
+fn display_name(name: &str) -> String {
+    for prefix in ["docker-", "libpod-", "cri-containerd-", "crio-"] {
+        if let Some(id) = name.strip_prefix(prefix).and_then(|rest| rest.strip_suffix(".scope"))
+            && id.chars().count() > 12
+        {
+            return format!("{}{}", prefix, id.chars().take(12).collect::<String>());
+        }
+    }
+    name.to_string()
+}
+
+/// Every cgroup below `path`, depth-first in name order. A child is a
+/// directory, told apart from the interface files by its `cgroup.procs`.
+fn walk(fs: &dyn SysFs, path: &str, depth: usize, cgroups: &mut Vec<Cgroup>) {
+    for entry in fs.read_dir(&format!("{}/{}", CGROUP_ROOT, path)) {
+        let child = if path.is_empty() { entry } else { format!("{}/{}", path, entry) };
+        if fs.read_to_string(&format!("{}/{}/cgroup.procs", CGROUP_ROOT, child)).is_none() {
+            continue;
+        }
+        let index = cgroups.len();
+        cgroups.push(Cgroup::read(fs, &child, depth));
+        walk(fs, &child, depth + 1, cgroups);
+        cgroups[index].has_children = cgroups.len() > index + 1;
+    }
+}
+
+/// Pids in a cgroup's `cgroup.procs`.
+fn read_procs(fs: &dyn SysFs, path: &str) -> Vec<u32> {
+    fs.read_to_string(&format!("{}/{}/cgroup.procs", CGROUP_ROOT, path))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .collect()
+}
+
+/// The cgroup v2 tree, with systemd units and containers as its leaves.
+/// Enter opens the process list of a cgroup and the cgroups nested in it,
+/// and charts that cgroup alone.
+pub struct ContainerCollector {
+    cgroups: Vec<Cgroup>,
+    last_refresh: Option<Instant>,
+    /// Selected row of the tree.
+    selected: usize,
+    /// Cgroup whose processes are listed, by path.
+    opened: Option<String>,
+    /// Process table of the opened cgroup.
+    processes: ProcessCollector,
+    /// Cgroup the process table was last read for, which lags `opened`
+    /// until the next refresh.
+    listed: Option<String>,
+}
+
+impl ContainerCollector {
+    /// `None` without a cgroup v2 (unified) hierarchy.
+    pub fn discover(fs: &dyn SysFs) -> Option<ContainerCollector> {
+        fs.read_to_string(&format!("{}/cgroup.controllers", CGROUP_ROOT))?;
+        Some(ContainerCollector {
+            cgroups: Vec::new(),
+            last_refresh: None,
+            selected: 0,
+            opened: None,
+            processes: ProcessCollector::new(fs),
+            listed: None,
+        })
+    }
+
+    /// Re-reads the tree. CPU and I/O are rates over the time since the
+    /// previous refresh; a cgroup seen for the first time has none yet.
+    fn refresh(&mut self, fs: &dyn SysFs, now: Instant) {
+        let elapsed = self.last_refresh.map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_refresh = Some(now);
+
+        let mut cgroups = Vec::new();
+        walk(fs, "", 0, &mut cgroups);
+        for cgroup in &mut cgroups {
+            let Some(before) = self.cgroups.iter().find(|before| before.path == cgroup.path) else {
+                continue;
+            };
+            if elapsed > 0.0 {
+                cgroup.cpu = cgroup.cpu_usage.saturating_sub(before.cpu_usage) as f64 / 1_000_000.0 / elapsed * 100.0;
+                cgroup.read_rate = cgroup.io_read.saturating_sub(before.io_read) as f64 / elapsed;
+                cgroup.write_rate = cgroup.io_write.saturating_sub(before.io_write) as f64 / elapsed;
+            }
+        }
+        self.cgroups = cgroups;
+        self.selected = self.selected.min(self.cgroups.len().saturating_sub(1));
+
+        if let Some(path) = &self.opened {
+            // Slices hold no processes themselves, so take those of every cgroup below too
+            let below = format!("{}/", path);
+            let pids: Vec<u32> = self.cgroups.iter()
+                .filter(|cgroup| cgroup.path == *path || cgroup.path.starts_with(&below))
+                .flat_map(|cgroup| read_procs(fs, &cgroup.path))
+                .collect();
+            self.processes.refresh_pids(fs, now, &pids);
+        }
+        self.listed = self.opened.clone();
+    }
+
+    /// Cgroups that get series: the opened one, or the busiest units and
+    /// containers. Slices are left out since they add up their children.
+    fn charted(&self) -> Vec<&Cgroup> {
+        match &self.opened {
+            Some(path) => self.cgroups.iter().filter(|cgroup| cgroup.path == *path).collect(),
+            None => {
+                let mut busiest: Vec<&Cgroup> = self.cgroups.iter().filter(|cgroup| !cgroup.has_children).collect();
+                busiest.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(b.memory.cmp(&a.memory)));
+                busiest.truncate(TOP_CGROUPS);
+                busiest
+            }
+        }
+    }
+}
+
+impl Collector for ContainerCollector {
+    fn name(&self) -> &str {
+        "Containers"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        // Same cadence as the Processes tab, except that opening a cgroup lists it right away
+        let now = Instant::now();
+        if self.last_refresh.is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL) || self.listed != self.opened {
+            self.refresh(fs, now);
+        }
+
+        for cgroup in self.charted() {
+            let series = |metric, unit| Series::new(metric, unit).label("cgroup", &cgroup.path).name(cgroup.name()).top_n();
+            samples.push(Sample::new(series("cgroup_cpu", Unit::Percent), cgroup.cpu));
+            samples.push(Sample::new(series("cgroup_memory", Unit::Byte), cgroup.memory as f64));
+            samples.push(Sample::new(
+                series("cgroup_read", Unit::ByteRate).name(format!("{} read", cgroup.name())),
+                cgroup.read_rate,
+            ));
+            samples.push(Sample::new(
+                series("cgroup_write", Unit::ByteRate).name(format!("{} write", cgroup.name())),
+                cgroup.write_rate,
+            ));
+            samples.push(Sample::new(series("cgroup_pids", Unit::Count), cgroup.pids as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let filter = |metric: &str| match &self.opened {
+            Some(path) => SeriesFilter::metric(metric).label("cgroup", path.clone()),
+            None => SeriesFilter::metric(metric),
+        };
+        let subject = match self.opened.as_deref().and_then(|path| self.cgroups.iter().find(|cgroup| cgroup.path == path)) {
+            Some(cgroup) => cgroup.name(),
+            None => format!("Top {} Units", TOP_CGROUPS),
+        };
+        vec![
+            vec![
+                ChartSpec::new(format!("{} CPU (%)", subject)).series(filter("cgroup_cpu")),
+                ChartSpec::new(format!("{} Memory (MiB)", subject)).series(filter("cgroup_memory")),
+            ],
+            vec![
+                ChartSpec::new(format!("{} I/O (MiB/s)", subject)).series(filter("cgroup_read")).series(filter("cgroup_write")),
+                ChartSpec::new(format!("{} Tasks", subject)).series(filter("cgroup_pids")),
+            ],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("cgroup_cpu")]
+    }
+
+    fn panel(&self) -> Option<Panel> {
+        if let Some(path) = &self.opened {
+            let mut panel = self.processes.panel()?;
+            if self.listed != self.opened {
+                panel.rows.clear();
+            }
+            panel.title = format!(
+                "{} - {} processes - ↑/↓ select, </> sort, i invert, t tree, / filter, Esc back",
+                path, panel.rows.len(),
+            );
+            return Some(panel);
+        }
+
+        let width = self.cgroups.iter()
+            .map(|cgroup| cgroup.depth * 2 + cgroup.name().len())
+            .max()
+            .unwrap_or(0)
+            .max("cgroup".len());
+        let header = format!(
+            "{:<width$} {:>6} {:>12} {:>12} {:>12} {:>12} {:>5}",
+            "cgroup", "CPU%", "memory", "limit", "read", "write", "tasks",
+        );
+        let rows = self.cgroups.iter()
+            .map(|cgroup| {
+                let name = format!("{}{}", "  ".repeat(cgroup.depth), cgroup.name());
+                let limit = cgroup.memory_max.map_or("max".to_string(), |max| Unit::Byte.format(max as f64));
+                format!(
+                    "{:<width$} {:>6.1} {:>12} {:>12} {:>12} {:>12} {:>5}",
+                    name, cgroup.cpu, Unit::Byte.format(cgroup.memory as f64), limit,
+                    Unit::ByteRate.format(cgroup.read_rate), Unit::ByteRate.format(cgroup.write_rate), cgroup.pids,
+                )
+            })
+            .collect();
+        let title = format!("Control Groups ({}) - ↑/↓ select, Enter processes", self.cgroups.len());
+        Some(Panel { title, header, rows, selected: self.selected })
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        if self.opened.is_some() {
+            // The process table takes everything but leaving, and Enter,
+            // which would pick a process to chart on the Processes tab
+            if self.processes.is_searching() || !matches!(key, KeyCode::Esc | KeyCode::Backspace | KeyCode::Enter) {
+                return self.processes.handle_key(key);
+            }
+            if key != KeyCode::Enter {
+                self.opened = None;
+            }
+            return true;
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected += 1,
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(20),
+            KeyCode::PageDown => self.selected += 20,
+            KeyCode::Enter => self.opened = self.cgroups.get(self.selected).map(|cgroup| cgroup.path.clone()),
+            _ => return false,
+        }
+        self.selected = self.selected.min(self.cgroups.len().saturating_sub(1));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::sysfs::{fixture, rewound};
+
+    #[test]
+    fn walks_the_tree() {
+        let fs = fixture("rk3588");
+        let mut cgroups = Vec::new();
+        walk(&fs, "", 0, &mut cgroups);
+        let tree: Vec<_> = cgroups.iter().map(|cgroup| (cgroup.name(), cgroup.depth, cgroup.has_children)).collect();
+        assert_eq!(tree, [
+            ("system.slice".to_string(), 0, true),
+            ("docker-3f9a2c1b7e4d".to_string(), 1, false),
+            ("rknn_server.service".to_string(), 1, false),
+            ("ssh.service".to_string(), 1, false),
+            ("user.slice".to_string(), 0, true),
+            ("user-1000.slice".to_string(), 1, true),
+            ("session-3.scope".to_string(), 2, false),
+        ]);
+
+        let docker = &cgroups[1];
+        assert_eq!((docker.memory, docker.memory_max, docker.pids), (104857600, Some(1073741824), 12));
+        assert_eq!((docker.io_read, docker.io_write), (20480000, 412090368));
+        assert_eq!(cgroups[2].memory_max, None);
+        assert_eq!(display_name("crio-ü0123456789abcdef.scope"), "crio-ü0123456789a");
+        assert!(ContainerCollector::discover(&fixture("rk3528")).is_none());
+    }
+
+    #[test]
+    fn opens_a_cgroup() {
+        let fs = fixture("rk3588");
+        let mut collector = ContainerCollector::discover(&fs).unwrap();
+        rewound(&mut collector, Duration::from_secs(2), |collector, now| collector.refresh(&fs, now), |collector| {
+            // The session used half a core and wrote 1 MiB/s
+            let session = collector.cgroups.iter_mut().find(|cgroup| cgroup.name() == "session-3.scope").unwrap();
+            session.cpu_usage -= 1_000_000;
+            session.io_write -= 2 * 1_048_576;
+        });
+
+        let charted: Vec<_> = collector.charted().iter().map(|cgroup| (cgroup.name(), cgroup.cpu, cgroup.write_rate)).collect();
+        assert_eq!(charted[0], ("session-3.scope".to_string(), 50.0, 1_048_576.0));
+        assert_eq!(charted.len(), 4);
+
+        for _ in 0..6 {
+            collector.handle_key(KeyCode::Down);
+        }
+        collector.handle_key(KeyCode::Enter);
+        // Later refreshes only need to come after the two above
+        let start = Instant::now();
+        collector.refresh(&fs, start + Duration::from_secs(3));
+        assert_eq!(collector.panel().unwrap().rows.len(), 2);
+        assert_eq!(collector.layout()[0][0].title, "session-3.scope CPU (%)");
+
+        collector.handle_key(KeyCode::Esc);
+        assert_eq!(collector.opened, None);
+        // A slice lists the processes of the cgroups nested in it
+        collector.handle_key(KeyCode::Up);
+        collector.handle_key(KeyCode::Up);
+        collector.handle_key(KeyCode::Enter);
+        assert_eq!(collector.opened.as_deref(), Some("user.slice"));
+        collector.refresh(&fs, start + Duration::from_secs(4));
+        assert_eq!(collector.panel().unwrap().rows.len(), 2);
+        collector.handle_key(KeyCode::Esc);
+        // Back on the tree, Esc goes on to the app and quits
+        assert!(!collector.handle_key(KeyCode::Esc));
+    }
+
+    #[test]
+    fn refreshes_on_the_process_cadence() {
+        let fs = fixture("rk3588");
+        let mut collector = ContainerCollector::discover(&fs).unwrap();
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        let first = collector.last_refresh;
+        collector.collect(&fs, &mut samples);
+        assert_eq!(collector.last_refresh, first);
+
+        // Opening a cgroup doesn't wait for the next refresh
+        collector.handle_key(KeyCode::Enter);
+        collector.collect(&fs, &mut samples);
+        assert_ne!(collector.last_refresh, first);
+        assert_eq!(collector.listed.as_deref(), Some("system.slice"));
+    }
+}
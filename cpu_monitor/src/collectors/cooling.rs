@@ -0,0 +1,155 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::hwmon::{ChannelKind, HwmonChannel, discover_hwmon_channels};
+
+const COOLING: &str = "/sys/class/thermal";
+
+/// A `cooling_deviceN` node: a fan, or a cpufreq/devfreq limiter used for
+/// passive cooling.
+#[derive(Debug, Clone)]
+struct CoolingDevice {
+    id: String,
+    kind: String,
+}
+
+impl CoolingDevice {
+    fn path(&self, attribute: &str) -> String {
+        format!("{}/{}/{}", COOLING, self.id, attribute)
+    }
+
+    /// Same test as `Dialog::findPwmFanDevice`, widened to `gpio-fan` and friends.
+    fn is_fan(&self) -> bool {
+        self.kind.contains("fan")
+    }
+
+    /// Current and maximum cooling state.
+    fn read(&self, fs: &dyn SysFs) -> (u32, u32) {
+        let state = |attribute| fs.read_to_string(&self.path(attribute))
+            .and_then(|content| content.trim().parse::<u32>().ok())
+            .unwrap_or(0);
+        (state("cur_state"), state("max_state"))
+    }
+}
+
+/// Sorts `<prefix>N` names by N, so `cooling_device10` comes after `cooling_device9`.
+fn sorted_by_number(names: Vec<String>, prefix: &str) -> Vec<String> {
+    let mut numbered: Vec<(usize, String)> = names.into_iter()
+        .filter_map(|name| Some((name.strip_prefix(prefix)?.parse().ok()?, name)))
+        .collect();
+    numbered.sort();
+    numbered.into_iter().map(|(_, name)| name).collect()
+}
+
+fn discover_cooling_devices(fs: &dyn SysFs) -> Vec<CoolingDevice> {
+    sorted_by_number(fs.read_dir(COOLING), "cooling_device")
+        .into_iter()
+        .map(|id| {
+            let kind = fs.read_to_string(&format!("{}/{}/type", COOLING, id))
+                .map(|kind| kind.trim().to_string())
+                .unwrap_or_else(|| id.clone());
+            CoolingDevice { id, kind }
+        })
+        .collect()
+}
+
+/// Every cooling device's state as a share of its maximum and the detected
+/// fan. Fan speed is charted from the Hwmon tab's `hwmon_fan` series.
+pub struct CoolingCollector {
+    devices: Vec<CoolingDevice>,
+    /// hwmon `fanN_input` tachometers, which decide whether to chart fan speed.
+    fans: Vec<HwmonChannel>,
+}
+
+impl CoolingCollector {
+    pub fn new(fs: &dyn SysFs) -> CoolingCollector {
+        let fans = discover_hwmon_channels(fs).into_iter().filter(|channel| channel.kind == ChannelKind::Fan).collect();
+        CoolingCollector { devices: discover_cooling_devices(fs), fans }
+    }
+
+    fn fan(&self) -> Option<&CoolingDevice> {
+        self.devices.iter().find(|device| device.is_fan())
+    }
+}
+
+impl Collector for CoolingCollector {
+    fn name(&self) -> &str {
+        "Cooling"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        for device in &self.devices {
+            let (state, max_state) = device.read(fs);
+            let level = if max_state > 0 { state as f64 / max_state as f64 * 100.0 } else { 0.0 };
+            let series = |metric, unit| Series::new(metric, unit)
+                .label("device", &device.id)
+                .label("type", &device.kind)
+                .name(&device.kind);
+            samples.push(Sample::new(series("cooling_level", Unit::Percent), level));
+            samples.push(Sample::new(series("cooling_state", Unit::Count), state as f64));
+            samples.push(Sample::new(series("cooling_max_state", Unit::Count), max_state as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let mut fan_row = Vec::new();
+        if let Some(fan) = self.fan() {
+            fan_row.push(ChartSpec::new(format!("Fan {} (%)", fan.kind))
+                .series(SeriesFilter::metric("cooling_level").label("device", &fan.id))
+                .y_bounds(0.0, 100.0));
+        }
+        if !self.fans.is_empty() {
+            fan_row.push(ChartSpec::new("Fan Speed (RPM)").series(SeriesFilter::metric("hwmon_fan")));
+        }
+
+        let mut layout = Vec::new();
+        if !fan_row.is_empty() {
+            layout.push(fan_row);
+        }
+        layout.push(vec![ChartSpec::new("Cooling Devices (% of max state)")
+            .series(SeriesFilter::metric("cooling_level"))
+            .y_bounds(0.0, 100.0)]);
+        layout
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("cooling_level")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, value};
+
+    #[test]
+    fn finds_the_fan_by_type() {
+        let collector = CoolingCollector::new(&fixture("rk3566"));
+        let types: Vec<_> = collector.devices.iter().map(|device| device.kind.as_str()).collect();
+        assert_eq!(types, ["cpufreq-cpu0", "devfreq-fde60000.gpu", "pwm-fan"]);
+        assert_eq!(collector.fan().unwrap().id, "cooling_device2");
+
+        assert!(CoolingCollector::new(&fixture("rk3528")).fan().is_none());
+    }
+
+    #[test]
+    fn scales_state_by_max_state() {
+        let fs = fixture("rk3566");
+        let mut collector = CoolingCollector::new(&fs);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+
+        assert_eq!(value(&samples, r#"cooling_level{device="cooling_device2",type="pwm-fan"}"#), 60.0);
+        assert_eq!(value(&samples, r#"cooling_max_state{device="cooling_device2",type="pwm-fan"}"#), 5.0);
+        assert!(samples.iter().all(|sample| sample.series.metric.starts_with("cooling_")));
+        assert!(collector.layout()[0][1].matches(&Series::new("hwmon_fan", Unit::Rpm)));
+    }
+
+    #[test]
+    fn fan_without_tachometer() {
+        let collector = CoolingCollector::new(&fixture("rk3588"));
+        assert!(collector.fans.is_empty());
+        assert_eq!(collector.fan().unwrap().read(&fixture("rk3588")), (1, 4));
+        assert_eq!(collector.layout()[0].len(), 1);
+    }
+}
@@ -0,0 +1,514 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+/// A group of cores sharing one cpufreq policy, e.g. the A76 or A55 cluster.
+#[derive(Debug, Clone)]
+struct CpuCluster {
+    name: String,
+    cpus: Vec<usize>,
+}
+
+/// Cumulative jiffies from a `cpu` line of /proc/stat.
+#[derive(Default, Debug, Clone, Copy)]
+struct CpuTimes {
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+}
+
+impl CpuTimes {
+    /// Parses the counters following the `cpu` label. Older kernels omit the
+    /// trailing fields, which are then treated as zero.
+    fn from_fields(fields: &[&str]) -> CpuTimes {
+        let field = |i: usize| fields.get(i).and_then(|v| v.parse().ok()).unwrap_or(0);
+        CpuTimes {
+            user: field(0),
+            nice: field(1),
+            system: field(2),
+            idle: field(3),
+            iowait: field(4),
+            irq: field(5),
+            softirq: field(6),
+            steal: field(7),
+        }
+    }
+
+    fn idle_time(&self) -> u64 {
+        self.idle + self.iowait
+    }
+
+    fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+}
+
+/// CPU utilisation over one sampling interval, in percent of elapsed jiffies.
+#[derive(Default, Debug, Clone, Copy)]
+struct CpuUsage {
+    total: f64,
+    user: f64,
+    system: f64,
+    iowait: f64,
+    irq: f64,
+    steal: f64,
+}
+
+impl CpuUsage {
+    /// Same accounting as `Dialog::calculateCPULoad`: iowait counts as idle time,
+    /// everything else that is not idle counts as load.
+    fn between(prev: &CpuTimes, curr: &CpuTimes) -> CpuUsage {
+        let elapsed = curr.total().saturating_sub(prev.total());
+        if elapsed == 0 {
+            return CpuUsage::default();
+        }
+        let percent = |curr: u64, prev: u64| curr.saturating_sub(prev) as f64 / elapsed as f64 * 100.0;
+
+        CpuUsage {
+            total: 100.0 - percent(curr.idle_time(), prev.idle_time()),
+            user: percent(curr.user + curr.nice, prev.user + prev.nice),
+            system: percent(curr.system, prev.system),
+            iowait: percent(curr.iowait, prev.iowait),
+            irq: percent(curr.irq + curr.softirq, prev.irq + prev.softirq),
+            steal: percent(curr.steal, prev.steal),
+        }
+    }
+}
+
+/// The aggregate `cpu` line and every online `cpuN` line of /proc/stat.
+#[derive(Default, Debug, Clone)]
+struct ProcStat {
+    total: CpuTimes,
+    cores: Vec<(usize, CpuTimes)>,
+}
+
+fn parse_proc_stat(content: &str) -> Option<ProcStat> {
+    let mut stat = ProcStat::default();
+    let mut found_total = false;
+
+    for line in content.lines().take_while(|line| line.starts_with("cpu")) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 {
+            continue;
+        }
+        let times = CpuTimes::from_fields(&parts[1..]);
+        if parts[0] == "cpu" {
+            stat.total = times;
+            found_total = true;
+        } else if let Ok(id) = parts[0]["cpu".len()..].parse::<usize>() {
+            stat.cores.push((id, times));
+        }
+    }
+
+    found_total.then_some(stat)
+}
+
+fn read_proc_stat(fs: &dyn SysFs) -> Option<ProcStat> {
+    parse_proc_stat(&fs.read_to_string("/proc/stat")?)
+}
+
+/// Jiffies counted on all cores together since boot, and the number of online
+/// cores, to turn per-process jiffies into a share of one core.
+pub(super) fn read_total_jiffies(fs: &dyn SysFs) -> Option<(u64, usize)> {
+    let stat = read_proc_stat(fs)?;
+    Some((stat.total.total(), stat.cores.len().max(1)))
+}
+
+/// Utilisation of the whole CPU and of each online core over one interval,
+/// `None` where there is no earlier snapshot to diff against.
+#[derive(Default, Debug, Clone)]
+struct CpuSample {
+    total: Option<CpuUsage>,
+    cores: Vec<(usize, Option<f64>)>,
+}
+
+/// Keeps the previous /proc/stat snapshot so each sample reports utilisation
+/// over the last interval rather than the average since boot.
+#[derive(Default)]
+struct CpuSampler {
+    prev: Option<ProcStat>,
+}
+
+impl CpuSampler {
+    /// The first call only primes the snapshot and reports no usage. Cores
+    /// that come online between two samples have none until the next one.
+    fn sample(&mut self, fs: &dyn SysFs) -> CpuSample {
+        let Some(curr) = read_proc_stat(fs) else {
+            return CpuSample::default();
+        };
+
+        let prev = self.prev.as_ref();
+        let sample = CpuSample {
+            total: prev.map(|prev| CpuUsage::between(&prev.total, &curr.total)),
+            cores: curr.cores.iter()
+                .map(|(id, times)| {
+                    let usage = prev
+                        .and_then(|prev| prev.cores.iter().find(|(prev_id, _)| prev_id == id))
+                        .map(|(_, prev_times)| CpuUsage::between(prev_times, times).total);
+                    (*id, usage)
+                })
+                .collect(),
+        };
+
+        self.prev = Some(curr);
+        sample
+    }
+}
+
+/// Parses a cpulist such as `0-3,6` as found in `related_cpus`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(cpu) = range.parse() {
+                    cpus.push(cpu);
+                }
+            }
+        }
+    }
+    cpus
+}
+
+/// Groups cores by cpufreq policy. `related_cpus` is a space separated list on
+/// most kernels, but some expose a cpulist range instead, so both are accepted.
+fn discover_cpu_clusters(fs: &dyn SysFs) -> Vec<CpuCluster> {
+    let mut clusters: Vec<CpuCluster> = Vec::new();
+
+    for name in fs.read_dir("/sys/devices/system/cpu/cpufreq") {
+        if !name.starts_with("policy") {
+            continue;
+        }
+        let related = fs.read_to_string(&format!("/sys/devices/system/cpu/cpufreq/{}/related_cpus", name))
+            .unwrap_or_default();
+        let mut cpus: Vec<usize> = related.split_whitespace().flat_map(parse_cpu_list).collect();
+        cpus.sort_unstable();
+        if !cpus.is_empty() {
+            clusters.push(CpuCluster { name, cpus });
+        }
+    }
+    clusters.sort_by_key(|cluster| cluster.cpus[0]);
+
+    // Without cpufreq, show every online core as one group
+    if clusters.is_empty()
+        && let Some(stat) = read_proc_stat(fs)
+        && !stat.cores.is_empty()
+    {
+        clusters.push(CpuCluster {
+            name: "cpu".to_string(),
+            cpus: stat.cores.iter().map(|(id, _)| *id).collect(),
+        });
+    }
+    clusters
+}
+
+fn get_core_frequency(fs: &dyn SysFs, cpu_id: usize) -> u64 {
+    let path = format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq", cpu_id);
+    if let Some(content) = fs.read_to_string(&path)
+        && let Ok(freq) = content.trim().parse::<u64>()
+    {
+        return freq * 1000; // Convert kHz to Hz
+    }
+    0
+}
+
+/// Governor, limits and residency of one cpufreq policy, with frequencies in Hz.
+#[derive(Default, Debug, Clone, PartialEq)]
+struct Policy {
+    governor: Option<String>,
+    /// Limits the governor has to stay within, `scaling_min_freq` and `scaling_max_freq`.
+    min: Option<u64>,
+    max: Option<u64>,
+    /// Highest frequency the hardware supports, `cpuinfo_max_freq`.
+    hardware_max: Option<u64>,
+    /// Frequency and time spent at it since boot in units of 10 ms, from
+    /// `stats/time_in_state`, which needs `CONFIG_CPU_FREQ_STAT`.
+    time_in_state: Vec<(u64, u64)>,
+}
+
+impl Policy {
+    /// A maximum below what the hardware supports, which is how the cpufreq
+    /// cooling device caps a hot cluster. A limit lowered by hand counts too.
+    fn throttled(&self) -> bool {
+        matches!((self.max, self.hardware_max), (Some(max), Some(hardware_max)) if max < hardware_max)
+    }
+}
+
+/// Parses `stats/time_in_state`, one `<kHz> <10 ms units>` line per frequency.
+fn parse_time_in_state(content: &str) -> Vec<(u64, u64)> {
+    content.lines()
+        .filter_map(|line| {
+            let (freq, time) = line.split_once(' ')?;
+            Some((freq.trim().parse::<u64>().ok()? * 1000, time.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn read_policy(fs: &dyn SysFs, name: &str) -> Policy {
+    let read = |file: &str| fs.read_to_string(&format!("/sys/devices/system/cpu/cpufreq/{}/{}", name, file));
+    let freq = |file: &str| read(file).and_then(|content| content.trim().parse::<u64>().ok()).map(|freq| freq * 1000);
+    Policy {
+        governor: read("scaling_governor").map(|governor| governor.trim().to_string()),
+        min: freq("scaling_min_freq"),
+        max: freq("scaling_max_freq"),
+        hardware_max: freq("cpuinfo_max_freq"),
+        time_in_state: read("stats/time_in_state").map(|content| parse_time_in_state(&content)).unwrap_or_default(),
+    }
+}
+
+/// Share of time spent at each frequency between two `time_in_state` readings,
+/// in percent. Without time between them, e.g. on the first interval, every
+/// share is zero, as the chart shows time since the start and not since boot.
+fn time_in_state_share(start: &[(u64, u64)], now: &[(u64, u64)]) -> Vec<(u64, f64)> {
+    let deltas: Vec<(u64, u64)> = now.iter()
+        .map(|&(freq, time)| {
+            let before = start.iter().find(|(other, _)| *other == freq).map_or(0, |(_, time)| *time);
+            (freq, time.saturating_sub(before))
+        })
+        .collect();
+    let total: u64 = deltas.iter().map(|(_, time)| time).sum();
+    deltas.iter()
+        .map(|&(freq, time)| (freq, if total > 0 { time as f64 / total as f64 * 100.0 } else { 0.0 }))
+        .collect()
+}
+
+/// Total and per-core utilisation plus per-core clocks, labelled by cluster,
+/// and the governor, limits, throttling and time in state of each policy.
+pub struct CpuCollector {
+    sampler: CpuSampler,
+    clusters: Vec<CpuCluster>,
+    /// `time_in_state` of each policy when the monitor started.
+    time_in_state_start: Vec<(String, Vec<(u64, u64)>)>,
+}
+
+impl CpuCollector {
+    pub fn new(fs: &dyn SysFs) -> CpuCollector {
+        let clusters = discover_cpu_clusters(fs);
+        let time_in_state_start = clusters.iter()
+            .map(|cluster| (cluster.name.clone(), read_policy(fs, &cluster.name).time_in_state))
+            .collect();
+        CpuCollector { sampler: CpuSampler::default(), clusters, time_in_state_start }
+    }
+
+    fn cluster_of(&self, cpu: usize) -> &str {
+        self.clusters.iter()
+            .find(|cluster| cluster.cpus.contains(&cpu))
+            .map(|cluster| cluster.name.as_str())
+            .unwrap_or("cpu")
+    }
+}
+
+impl Collector for CpuCollector {
+    fn name(&self) -> &str {
+        "CPU"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let cpu = self.sampler.sample(fs);
+
+        if let Some(total) = &cpu.total {
+            samples.push(Sample::new(Series::new("cpu_usage", Unit::Percent).name("Total"), total.total));
+            for (mode, name, value) in [
+                ("user", "User", total.user),
+                ("system", "System", total.system),
+                ("iowait", "IOwait", total.iowait),
+                ("irq", "IRQ", total.irq),
+                ("steal", "Steal", total.steal),
+            ] {
+                samples.push(Sample::new(Series::new("cpu_mode_usage", Unit::Percent).label("mode", mode).name(name), value));
+            }
+        }
+
+        for &(id, usage) in &cpu.cores {
+            let cluster = self.cluster_of(id);
+            let core = |metric, unit| Series::new(metric, unit)
+                .label("cluster", cluster)
+                .label("core", id)
+                .name(format!("cpu{}", id));
+            if let Some(usage) = usage {
+                samples.push(Sample::new(core("cpu_core_usage", Unit::Percent), usage));
+            }
+            samples.push(Sample::new(core("cpu_core_frequency", Unit::Hertz), get_core_frequency(fs, id) as f64));
+        }
+
+        // Cores of one policy share a clock, so the first core stands for the cluster
+        for cluster in &self.clusters {
+            let freq = get_core_frequency(fs, cluster.cpus[0]);
+            let policy = read_policy(fs, &cluster.name);
+            let series = |metric, unit| Series::new(metric, unit).label("cluster", &cluster.name);
+
+            let name = match &policy.governor {
+                Some(governor) => format!("{} ({})", cluster.name, governor),
+                None => cluster.name.clone(),
+            };
+            samples.push(Sample::new(series("cpu_cluster_frequency", Unit::Hertz).name(name), freq as f64));
+
+            for (metric, suffix, value) in [
+                ("cpu_cluster_min_frequency", "min", policy.min),
+                ("cpu_cluster_max_frequency", "max", policy.max),
+                ("cpu_cluster_hardware_max_frequency", "hardware max", policy.hardware_max),
+            ] {
+                if let Some(value) = value {
+                    samples.push(Sample::new(series(metric, Unit::Hertz).name(format!("{} {}", cluster.name, suffix)), value as f64));
+                }
+            }
+            if policy.max.is_some() && policy.hardware_max.is_some() {
+                samples.push(Sample::new(
+                    series("cpu_cluster_throttled", Unit::Count).name(format!("{} throttled", cluster.name)),
+                    if policy.throttled() { 1.0 } else { 0.0 },
+                ));
+            }
+
+            let start = self.time_in_state_start.iter()
+                .find(|(name, _)| *name == cluster.name)
+                .map_or(&[][..], |(_, start)| start.as_slice());
+            for (freq, share) in time_in_state_share(start, &policy.time_in_state) {
+                samples.push(Sample::new(
+                    series("cpu_time_in_state", Unit::Percent).label("frequency", freq).name((freq / 1_000_000).to_string()),
+                    share,
+                ));
+            }
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        // Usage chart with the per-category breakdown overlaid on the total
+        let mut layout = vec![vec![
+            ChartSpec::new("CPU Usage (%)")
+                .series(SeriesFilter::metric("cpu_usage"))
+                .series(SeriesFilter::metric("cpu_mode_usage"))
+                .y_bounds(0.0, 100.0),
+        ]];
+
+        // One row per cpufreq policy: per-core usage next to the cluster clock
+        // and its limits, with throttled intervals marked, and the time in state
+        for cluster in &self.clusters {
+            let filter = |metric| SeriesFilter::metric(metric).label("cluster", &cluster.name);
+            layout.push(vec![
+                ChartSpec::new(format!("{} Core Usage (%)", cluster.name))
+                    .series(filter("cpu_core_usage"))
+                    .y_bounds(0.0, 100.0),
+                ChartSpec::new(format!("{} Frequency (MHz)", cluster.name))
+                    .series(filter("cpu_cluster_frequency"))
+                    .threshold(filter("cpu_cluster_min_frequency"))
+                    .threshold(filter("cpu_cluster_max_frequency"))
+                    .threshold(filter("cpu_cluster_hardware_max_frequency"))
+                    .mark(filter("cpu_cluster_throttled")),
+                ChartSpec::new(format!("{} Time in State since Start (%, MHz)", cluster.name))
+                    .series(filter("cpu_time_in_state"))
+                    .bars(),
+            ]);
+        }
+        layout
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![
+            SeriesFilter::metric("cpu_usage"),
+            SeriesFilter::metric("cpu_mode_usage"),
+            SeriesFilter::metric("cpu_cluster_frequency"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, sample};
+
+    #[test]
+    fn parses_proc_stat() {
+        let stat = read_proc_stat(&fixture("rk3588")).unwrap();
+        assert_eq!(stat.cores.len(), 8);
+        assert_eq!(stat.cores[4].0, 4);
+        assert_eq!(stat.total.user, 4 * 81234 + 4 * 120345);
+        assert_eq!(stat.total.steal, 0);
+    }
+
+    #[test]
+    fn usage_counts_iowait_as_idle() {
+        let prev = CpuTimes::from_fields(&["100", "0", "50", "800", "50", "0", "0", "0"]);
+        let curr = CpuTimes::from_fields(&["160", "0", "70", "880", "90", "0", "0", "0"]);
+        let usage = CpuUsage::between(&prev, &curr);
+        assert_eq!(usage.total, 40.0);
+        assert_eq!(usage.user, 30.0);
+        assert_eq!(usage.system, 10.0);
+        assert_eq!(usage.iowait, 20.0);
+    }
+
+    #[test]
+    fn usage_waits_for_a_second_snapshot() {
+        let fs = fixture("rk3588");
+        let mut collector = CpuCollector::new(&fs);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        assert!(!samples.iter().any(|sample| sample.series.metric.ends_with("_usage")));
+        assert!(samples.iter().any(|sample| sample.series.metric == "cpu_core_frequency"));
+
+        samples.clear();
+        collector.collect(&fs, &mut samples);
+        assert_eq!(samples.iter().filter(|sample| sample.series.metric == "cpu_core_usage").count(), 8);
+        assert!(samples.iter().any(|sample| sample.series.key() == r#"cpu_mode_usage{mode="iowait"}"#));
+    }
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,6"), vec![0, 1, 2, 3, 6]);
+        assert_eq!(parse_cpu_list("4\n"), vec![4]);
+    }
+
+    #[test]
+    fn groups_cores_by_policy() {
+        let clusters = discover_cpu_clusters(&fixture("rk3588"));
+        let cpus: Vec<_> = clusters.iter().map(|cluster| (cluster.name.as_str(), cluster.cpus.clone())).collect();
+        assert_eq!(cpus, vec![("policy0", vec![0, 1, 2, 3]), ("policy4", vec![4, 5]), ("policy6", vec![6, 7])]);
+
+        assert_eq!(discover_cpu_clusters(&fixture("rk3566")).len(), 1);
+        assert_eq!(get_core_frequency(&fixture("rk3588"), 4), 2_352_000_000);
+    }
+
+    #[test]
+    fn reads_policy_limits_and_throttling() {
+        let fs = fixture("rk3588");
+        let policy = read_policy(&fs, "policy6");
+        assert_eq!(policy.governor.as_deref(), Some("performance"));
+        assert_eq!((policy.min, policy.max, policy.hardware_max), (Some(408_000_000), Some(2_256_000_000), Some(2_352_000_000)));
+        assert_eq!(policy.time_in_state[10], (2_256_000_000, 39634));
+        assert!(policy.throttled());
+        assert!(!read_policy(&fs, "policy4").throttled());
+
+        // No limits or stats in this capture
+        assert_eq!(read_policy(&fixture("rk3566"), "policy0"), Policy::default());
+    }
+
+    #[test]
+    fn shares_time_in_state_since_start() {
+        let start = [(408_000_000, 900), (1_800_000_000, 100)];
+        assert_eq!(time_in_state_share(&start, &start), [(408_000_000, 0.0), (1_800_000_000, 0.0)]);
+        let now = [(408_000_000, 930), (1_800_000_000, 190)];
+        assert_eq!(time_in_state_share(&start, &now), [(408_000_000, 25.0), (1_800_000_000, 75.0)]);
+    }
+
+    #[test]
+    fn marks_throttled_clusters() {
+        let fs = fixture("rk3588");
+        let mut collector = CpuCollector::new(&fs);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        assert_eq!(sample(&samples, r#"cpu_cluster_frequency{cluster="policy6"}"#).series.name, "policy6 (performance)");
+        assert_eq!(sample(&samples, r#"cpu_cluster_throttled{cluster="policy6"}"#).value, 1.0);
+        assert_eq!(sample(&samples, r#"cpu_cluster_throttled{cluster="policy4"}"#).value, 0.0);
+        assert_eq!(sample(&samples, r#"cpu_cluster_max_frequency{cluster="policy6"}"#).value, 2_256_000_000.0);
+        assert_eq!(sample(&samples, r#"cpu_time_in_state{cluster="policy0",frequency="1800000000"}"#).series.name, "1800");
+    }
+}
@@ -0,0 +1,224 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+/// What a devfreq node drives, derived from its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevfreqKind {
+    Gpu,
+    Npu,
+    Dmc,
+    Rkvenc,
+    Rkvdec,
+    Rga,
+    /// Hantro VDPU/VEPU and the AV1 decoder.
+    Vpu,
+    Jpeg,
+    Other,
+}
+
+impl DevfreqKind {
+    /// Node names are `<address>.<block>` (`fb000000.gpu`, `fdab0000.npu`) or a
+    /// bare block name such as `dmc`. Also used for mpp_service device and
+    /// clock names, e.g. `RKJPEGD` or `clk_rkvdec0_core`.
+    pub fn classify(name: &str) -> DevfreqKind {
+        let block = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+        if block.contains("jpeg") {
+            DevfreqKind::Jpeg
+        } else if block.contains("gpu") {
+            DevfreqKind::Gpu
+        } else if block.contains("npu") {
+            DevfreqKind::Npu
+        } else if block.contains("dmc") {
+            DevfreqKind::Dmc
+        } else if block.contains("venc") {
+            DevfreqKind::Rkvenc
+        } else if block.contains("vdec") || block.contains("hevc") {
+            DevfreqKind::Rkvdec
+        } else if block.contains("rga") {
+            DevfreqKind::Rga
+        } else if ["vdpu", "vepu", "vpu", "av1"].iter().any(|vpu| block.contains(vpu)) {
+            DevfreqKind::Vpu
+        } else {
+            DevfreqKind::Other
+        }
+    }
+
+    /// Video decoders and encoders, shown on the Video tab.
+    pub fn is_video(&self) -> bool {
+        matches!(self, DevfreqKind::Rkvenc | DevfreqKind::Rkvdec | DevfreqKind::Vpu | DevfreqKind::Jpeg)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DevfreqKind::Gpu => "GPU",
+            DevfreqKind::Npu => "NPU",
+            DevfreqKind::Dmc => "DMC",
+            DevfreqKind::Rkvenc => "RKVENC",
+            DevfreqKind::Rkvdec => "RKVDEC",
+            DevfreqKind::Rga => "RGA",
+            DevfreqKind::Vpu => "VPU",
+            DevfreqKind::Jpeg => "JPEG",
+            DevfreqKind::Other => "devfreq",
+        }
+    }
+}
+
+/// A node under /sys/class/devfreq found at startup.
+#[derive(Debug, Clone)]
+pub struct DevfreqDevice {
+    pub name: String,
+    pub kind: DevfreqKind,
+}
+
+impl DevfreqDevice {
+    fn path(&self, attribute: &str) -> String {
+        format!("/sys/class/devfreq/{}/{}", self.name, attribute)
+    }
+
+    /// Reads `load` ("12@600000000Hz") and `cur_freq`. Load stays zero for
+    /// governors that don't report it.
+    pub fn read(&self, fs: &dyn SysFs) -> (f64, u64) {
+        let load = fs.read_to_string(&self.path("load"))
+            .and_then(|content| content.split('@').next().and_then(|load| load.trim().parse::<f64>().ok()))
+            .unwrap_or(0.0);
+        let freq = fs.read_to_string(&self.path("cur_freq"))
+            .and_then(|content| content.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        (load, freq)
+    }
+
+    /// Active governor, e.g. `dmc_ondemand` or `userspace`.
+    pub fn governor(&self, fs: &dyn SysFs) -> Option<String> {
+        fs.read_to_string(&self.path("governor"))
+            .map(|governor| governor.trim().to_string())
+            .filter(|governor| !governor.is_empty())
+    }
+}
+
+pub fn discover_devfreq_devices(fs: &dyn SysFs) -> Vec<DevfreqDevice> {
+    fs.read_dir("/sys/class/devfreq")
+        .into_iter()
+        .map(|name| DevfreqDevice { kind: DevfreqKind::classify(&name), name })
+        .collect()
+}
+
+/// Load and clock of every devfreq node, one chart row per node.
+pub struct DevfreqCollector {
+    devices: Vec<DevfreqDevice>,
+}
+
+impl DevfreqCollector {
+    pub fn new(devices: Vec<DevfreqDevice>) -> DevfreqCollector {
+        DevfreqCollector { devices }
+    }
+}
+
+impl Collector for DevfreqCollector {
+    fn name(&self) -> &str {
+        "Devfreq"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        for device in &self.devices {
+            let (load, freq) = device.read(fs);
+            let series = |metric, unit, suffix| Series::new(metric, unit)
+                .label("device", &device.name)
+                .label("kind", device.kind.label())
+                .name(format!("{} {}", device.kind.label(), suffix));
+            samples.push(Sample::new(series("devfreq_load", Unit::Percent, "load"), load));
+            samples.push(Sample::new(series("devfreq_frequency", Unit::Hertz, "clock"), freq as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        self.devices.iter()
+            .map(|device| vec![
+                ChartSpec::new(format!("{} {} Load (%)", device.kind.label(), device.name))
+                    .series(SeriesFilter::metric("devfreq_load").label("device", &device.name))
+                    .y_bounds(0.0, 100.0),
+                ChartSpec::new(format!("{} {} Frequency (MHz)", device.kind.label(), device.name))
+                    .series(SeriesFilter::metric("devfreq_frequency").label("device", &device.name)),
+            ])
+            .collect()
+    }
+}
+
+/// GPU load and clock from the first `*.gpu` devfreq node, with NVML as the
+/// fallback on boards without one.
+pub struct GpuCollector {
+    device: Option<DevfreqDevice>,
+}
+
+impl GpuCollector {
+    pub fn discover(devices: &[DevfreqDevice]) -> Option<GpuCollector> {
+        let device = devices.iter().find(|device| device.kind == DevfreqKind::Gpu).cloned();
+        if device.is_none() && nvml_wrapper::Nvml::init().is_err() {
+            return None;
+        }
+        Some(GpuCollector { device })
+    }
+}
+
+fn get_nvml_gpu_usage() -> f64 {
+    if let Ok(nvml) = nvml_wrapper::Nvml::init()
+        && let Ok(device_count) = nvml.device_count()
+        && device_count > 0
+        && let Ok(device) = nvml.device_by_index(0)
+        && let Ok(utilization) = device.utilization_rates()
+    {
+        return utilization.gpu as f64;
+    }
+    0.0
+}
+
+impl Collector for GpuCollector {
+    fn name(&self) -> &str {
+        "GPU"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let (load, freq) = match &self.device {
+            Some(device) => device.read(fs),
+            None => (get_nvml_gpu_usage(), 0),
+        };
+        samples.push(Sample::new(Series::new("gpu_usage", Unit::Percent).name("GPU Usage"), load));
+        samples.push(Sample::new(Series::new("gpu_frequency", Unit::Hertz).name("GPU Frequency"), freq as f64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+
+    #[test]
+    fn classifies_node_names() {
+        assert_eq!(DevfreqKind::classify("fb000000.gpu"), DevfreqKind::Gpu);
+        assert_eq!(DevfreqKind::classify("fdab0000.npu"), DevfreqKind::Npu);
+        assert_eq!(DevfreqKind::classify("dmc"), DevfreqKind::Dmc);
+        assert_eq!(DevfreqKind::classify("fdc38100.rkvdec"), DevfreqKind::Rkvdec);
+        assert_eq!(DevfreqKind::classify("fdbd0000.rkvenc-core"), DevfreqKind::Rkvenc);
+        assert_eq!(DevfreqKind::classify("fdb50400.vdpu"), DevfreqKind::Vpu);
+        assert_eq!(DevfreqKind::classify("aclk_jpeg_decoder"), DevfreqKind::Jpeg);
+        assert_eq!(DevfreqKind::classify("ff9a0000.bus"), DevfreqKind::Other);
+    }
+
+    #[test]
+    fn discovers_and_reads_nodes() {
+        let fs = fixture("rk3588");
+        let devices = discover_devfreq_devices(&fs);
+        let kinds: Vec<_> = devices.iter().map(|device| device.kind).collect();
+        assert_eq!(kinds, vec![DevfreqKind::Dmc, DevfreqKind::Gpu, DevfreqKind::Npu, DevfreqKind::Rkvenc, DevfreqKind::Rkvdec]);
+        assert_eq!(devices[1].read(&fs), (23.0, 1_000_000_000));
+        assert_eq!(devices[1].governor(&fs).as_deref(), Some("simple_ondemand"));
+        assert_eq!(devices[2].governor(&fs), None);
+    }
+
+    #[test]
+    fn missing_load_reads_as_zero() {
+        let fs = fixture("rk3566");
+        let npu = discover_devfreq_devices(&fs).into_iter().find(|device| device.kind == DevfreqKind::Npu).unwrap();
+        assert_eq!(npu.read(&fs), (0.0, 600_000_000));
+    }
+}
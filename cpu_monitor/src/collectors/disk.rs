@@ -0,0 +1,258 @@
+use std::time::Instant;
+
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+/// `/proc/diskstats` counts sectors of 512 bytes whatever the device's own
+/// sector size.
+const SECTOR_SIZE: u64 = 512;
+
+/// Cumulative counters of one block device from `/proc/diskstats`.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct DiskCounters {
+    reads: u64,
+    read_sectors: u64,
+    /// Milliseconds spent on reads, summed over all of them.
+    read_ticks: u64,
+    writes: u64,
+    write_sectors: u64,
+    write_ticks: u64,
+    /// Milliseconds during which at least one request was in flight.
+    io_ticks: u64,
+}
+
+/// Parses `/proc/diskstats` rows: `major minor name` followed by the read,
+/// merge, sector and time counters of reads, then of writes, then the queue.
+fn parse_diskstats(content: &str) -> Vec<(String, DiskCounters)> {
+    content.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let counter = |index: usize| fields.get(index).and_then(|value| value.parse::<u64>().ok());
+            Some((fields.get(2)?.to_string(), DiskCounters {
+                reads: counter(3)?,
+                read_sectors: counter(5)?,
+                read_ticks: counter(6)?,
+                writes: counter(7)?,
+                write_sectors: counter(9)?,
+                write_ticks: counter(10)?,
+                io_ticks: counter(12)?,
+            }))
+        })
+        .collect()
+}
+
+/// Whole disks with I/O from diskstats. Partitions aren't in `/sys/block`,
+/// loop, ram and zram devices aren't storage, and eMMC boot and RPMB areas
+/// that were never read are left out.
+fn read_disks(fs: &dyn SysFs) -> Vec<(String, DiskCounters)> {
+    let Some(content) = fs.read_to_string("/proc/diskstats") else {
+        return Vec::new();
+    };
+    let disks = fs.read_dir("/sys/block");
+    parse_diskstats(&content)
+        .into_iter()
+        .filter(|(name, counters)| {
+            disks.contains(name)
+                && !["loop", "ram", "zram"].iter().any(|prefix| name.starts_with(prefix))
+                && counters.reads + counters.writes > 0
+        })
+        .collect()
+}
+
+/// Block device and mount point of a mounted filesystem.
+#[derive(Debug, Clone, PartialEq)]
+struct Mount {
+    device: String,
+    path: String,
+    fs_type: String,
+}
+
+/// Filesystems on block devices in `/proc/mounts`, once per device so bind
+/// mounts don't repeat the root filesystem. Spaces and other special
+/// characters in paths are escaped as octal, e.g. `\040`.
+fn parse_mounts(content: &str) -> Vec<Mount> {
+    let mut mounts: Vec<Mount> = Vec::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [device, path, fs_type, ..] = fields[..] else {
+            continue;
+        };
+        if !device.starts_with("/dev/") || mounts.iter().any(|mount| mount.device == device) {
+            continue;
+        }
+        mounts.push(Mount { device: device.to_string(), path: unescape(path), fs_type: fs_type.to_string() });
+    }
+    mounts
+}
+
+fn unescape(path: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let octal = tail.get(..3).and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match octal {
+            Some(value) if byte == b'\\' => {
+                bytes.push(value);
+                rest = &tail[3..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Per-disk throughput, IOPS, utilisation and average wait from
+/// /proc/diskstats, and the space used on every mounted filesystem.
+pub struct DiskCollector {
+    last: Option<(Instant, Vec<(String, DiskCounters)>)>,
+}
+
+impl DiskCollector {
+    /// `None` when there is neither a disk nor a mounted block device.
+    pub fn discover(fs: &dyn SysFs) -> Option<DiskCollector> {
+        let mounts = fs.read_to_string("/proc/mounts").map(|content| parse_mounts(&content)).unwrap_or_default();
+        if read_disks(fs).is_empty() && mounts.is_empty() {
+            return None;
+        }
+        Some(DiskCollector { last: None })
+    }
+
+    fn collect_at(&mut self, fs: &dyn SysFs, now: Instant, samples: &mut Vec<Sample>) {
+        let disks = read_disks(fs);
+        if let Some((last_time, last)) = &self.last {
+            let elapsed = now.duration_since(*last_time).as_secs_f64();
+            for (name, counters) in disks.iter().filter(|_| elapsed > 0.0) {
+                let Some((_, before)) = last.iter().find(|(other, _)| other == name) else {
+                    continue;
+                };
+                let delta = |now: u64, before: u64| now.saturating_sub(before);
+                let reads = delta(counters.reads, before.reads);
+                let writes = delta(counters.writes, before.writes);
+                let ticks = delta(counters.read_ticks, before.read_ticks) + delta(counters.write_ticks, before.write_ticks);
+                // Average time a request took, queueing included, as iostat's await
+                let wait = if reads + writes > 0 { ticks as f64 / (reads + writes) as f64 / 1000.0 } else { 0.0 };
+                let busy = delta(counters.io_ticks, before.io_ticks) as f64 / 1000.0 / elapsed * 100.0;
+
+                let series = |metric, unit| Series::new(metric, unit).label("device", name).name(name);
+                for (series, value) in [
+                    (series("disk_read", Unit::ByteRate), (delta(counters.read_sectors, before.read_sectors) * SECTOR_SIZE) as f64 / elapsed),
+                    (series("disk_write", Unit::ByteRate), (delta(counters.write_sectors, before.write_sectors) * SECTOR_SIZE) as f64 / elapsed),
+                    (series("disk_reads", Unit::PerSecond).name(format!("{} reads", name)), reads as f64 / elapsed),
+                    (series("disk_writes", Unit::PerSecond).name(format!("{} writes", name)), writes as f64 / elapsed),
+                    (series("disk_utilization", Unit::Percent), busy.min(100.0)),
+                    (series("disk_await", Unit::Second), wait),
+                ] {
+                    samples.push(Sample::new(series, value));
+                }
+            }
+        }
+        self.last = Some((now, disks));
+
+        let mounts = fs.read_to_string("/proc/mounts").map(|content| parse_mounts(&content)).unwrap_or_default();
+        for mount in mounts {
+            let Some(space) = fs.statvfs(&mount.path) else {
+                continue;
+            };
+            // Like df, the root reserve counts as neither used nor available
+            let used = space.total.saturating_sub(space.free);
+            let usable = used + space.available;
+            let usage = if usable > 0 { used as f64 / usable as f64 * 100.0 } else { 0.0 };
+            let series = |metric, unit| Series::new(metric, unit)
+                .label("mount", &mount.path)
+                .name(format!("{} ({}, {})", mount.path, mount.fs_type, mount.device.trim_start_matches("/dev/")));
+            samples.push(Sample::new(series("fs_usage", Unit::Percent), usage));
+            samples.push(Sample::new(series("fs_used", Unit::Byte), used as f64));
+            samples.push(Sample::new(series("fs_size", Unit::Byte), space.total as f64));
+        }
+    }
+}
+
+impl Collector for DiskCollector {
+    fn name(&self) -> &str {
+        "Disk"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        self.collect_at(fs, Instant::now(), samples);
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        vec![
+            vec![
+                ChartSpec::new("Disk Read (MiB/s)").series(SeriesFilter::metric("disk_read")),
+                ChartSpec::new("Disk Write (MiB/s)").series(SeriesFilter::metric("disk_write")),
+            ],
+            vec![
+                ChartSpec::new("IOPS (/s)").series(SeriesFilter::metric("disk_reads")).series(SeriesFilter::metric("disk_writes")),
+                // I/O stalls on eMMC and SD cards show up as CPU iowait
+                ChartSpec::new("Utilisation and CPU IOwait (%)")
+                    .series(SeriesFilter::metric("disk_utilization"))
+                    .series(SeriesFilter::metric("cpu_mode_usage").label("mode", "iowait"))
+                    .y_bounds(0.0, 100.0),
+                ChartSpec::new("Await (ms)").series(SeriesFilter::metric("disk_await")),
+            ],
+            vec![ChartSpec::new("Filesystem Usage (%)").series(SeriesFilter::metric("fs_usage")).y_bounds(0.0, 100.0)],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("disk_utilization"), SeriesFilter::metric("fs_usage")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::sysfs::{fixture, rewound, value};
+
+    #[test]
+    fn finds_disks_and_mounts() {
+        let fs = fixture("rk3588");
+        let disks: Vec<_> = read_disks(&fs).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(disks, ["mmcblk0", "nvme0n1"]);
+
+        let mounts = parse_mounts(&fs.read_to_string("/proc/mounts").unwrap());
+        let paths: Vec<_> = mounts.iter().map(|mount| mount.path.as_str()).collect();
+        assert_eq!(paths, ["/", "/boot/firmware", "/mnt/camera footage"]);
+        assert_eq!(mounts[1].fs_type, "vfat");
+
+        assert!(DiskCollector::discover(&fixture("rk3528")).is_none());
+    }
+
+    #[test]
+    fn computes_rates_and_wait() {
+        let fs = fixture("rk3588");
+        let mut collector = DiskCollector::discover(&fs).unwrap();
+        let read = |collector: &mut DiskCollector, now| {
+            let mut samples = Vec::new();
+            collector.collect_at(&fs, now, &mut samples);
+            samples
+        };
+        let (first, samples) = rewound(&mut collector, Duration::from_secs(2), read, |collector| {
+            // The eMMC did 200 reads of 8 sectors and 100 writes, taking
+            // 900 ms in total and busy for 500 ms
+            let (_, last) = collector.last.as_mut().unwrap();
+            let emmc = &mut last[0].1;
+            emmc.reads -= 200;
+            emmc.read_sectors -= 1600;
+            emmc.writes -= 100;
+            emmc.read_ticks -= 600;
+            emmc.write_ticks -= 300;
+            emmc.io_ticks -= 500;
+        });
+        // statvfs of the fixture root reports whatever disk the tests run from
+        assert!(first.iter().any(|sample| sample.series.key() == r#"fs_size{mount="/"}"#));
+
+        assert_eq!(value(&samples, r#"disk_read{device="mmcblk0"}"#), 409_600.0);
+        assert_eq!(value(&samples, r#"disk_reads{device="mmcblk0"}"#), 100.0);
+        assert_eq!(value(&samples, r#"disk_writes{device="mmcblk0"}"#), 50.0);
+        assert_eq!(value(&samples, r#"disk_utilization{device="mmcblk0"}"#), 25.0);
+        assert_eq!(Unit::Second.format(value(&samples, r#"disk_await{device="mmcblk0"}"#)), "3.0 ms");
+        assert_eq!(value(&samples, r#"disk_write{device="nvme0n1"}"#), 0.0);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+const HWMON: &str = "/sys/class/hwmon";
+
+/// Channel types of the hwmon sysfs ABI that are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    Temp,
+    In,
+    Curr,
+    Power,
+    Fan,
+}
+
+impl ChannelKind {
+    const ALL: [ChannelKind; 5] = [ChannelKind::Temp, ChannelKind::In, ChannelKind::Curr, ChannelKind::Power, ChannelKind::Fan];
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            ChannelKind::Temp => "temp",
+            ChannelKind::In => "in",
+            ChannelKind::Curr => "curr",
+            ChannelKind::Power => "power",
+            ChannelKind::Fan => "fan",
+        }
+    }
+
+    /// Metric name, unit and divisor from the sysfs value to the base unit.
+    fn metric(&self) -> (&'static str, Unit, f64) {
+        match self {
+            ChannelKind::Temp => ("hwmon_temperature", Unit::Celsius, 1000.0), // millidegrees
+            ChannelKind::In => ("hwmon_voltage", Unit::Volt, 1000.0),          // millivolts
+            ChannelKind::Curr => ("hwmon_current", Unit::Ampere, 1000.0),      // milliamperes
+            ChannelKind::Power => ("hwmon_power", Unit::Watt, 1_000_000.0),    // microwatts
+            ChannelKind::Fan => ("hwmon_fan", Unit::Rpm, 1.0),
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            ChannelKind::Temp => "Temperature (°C)",
+            ChannelKind::In => "Voltage (V)",
+            ChannelKind::Curr => "Current (A)",
+            ChannelKind::Power => "Power (W)",
+            ChannelKind::Fan => "Fan Speed (RPM)",
+        }
+    }
+
+    /// Splits an input file name such as `curr1_input` into kind and channel.
+    fn parse(file: &str) -> Option<(ChannelKind, &str)> {
+        let channel = file.strip_suffix("_input")?;
+        let kind = ChannelKind::ALL.into_iter().find(|kind| {
+            channel.strip_prefix(kind.prefix()).is_some_and(|index| index.parse::<u32>().is_ok())
+        })?;
+        Some((kind, channel))
+    }
+}
+
+/// One `<type><N>_input` file of a hwmon device.
+#[derive(Debug, Clone)]
+pub struct HwmonChannel {
+    pub hwmon: String,
+    /// Chip name, or its device-tree label when it has one.
+    pub chip: String,
+    pub channel: String,
+    pub kind: ChannelKind,
+    /// The channel's `_label`, or `<chip> <channel>`.
+    pub name: String,
+}
+
+impl HwmonChannel {
+    /// Current reading in the base unit of its kind.
+    pub fn read(&self, fs: &dyn SysFs) -> Option<f64> {
+        let raw = fs.read_to_string(&format!("{}/{}/{}_input", HWMON, self.hwmon, self.channel))?;
+        Some(raw.trim().parse::<f64>().ok()? / self.kind.metric().2)
+    }
+
+    pub fn series(&self) -> Series {
+        let (metric, unit, _) = self.kind.metric();
+        Series::new(metric, unit)
+            .label("hwmon", &self.hwmon)
+            .label("chip", &self.chip)
+            .label("channel", &self.channel)
+            .name(&self.name)
+    }
+}
+
+/// Every readable channel of every hwmon device, in device and channel order.
+pub fn discover_hwmon_channels(fs: &dyn SysFs) -> Vec<HwmonChannel> {
+    let mut devices: Vec<(usize, String)> = fs.read_dir(HWMON)
+        .into_iter()
+        .filter_map(|hwmon| Some((hwmon.strip_prefix("hwmon")?.parse().ok()?, hwmon)))
+        .collect();
+    devices.sort();
+
+    let read = |hwmon: &str, file: &str| fs.read_to_string(&format!("{}/{}/{}", HWMON, hwmon, file))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty());
+    let chips: Vec<String> = devices.iter()
+        .map(|(_, hwmon)| read(hwmon, "label").or_else(|| read(hwmon, "name")).unwrap_or_else(|| hwmon.clone()))
+        .collect();
+
+    let mut channels = Vec::new();
+    for ((_, hwmon), chip) in devices.iter().zip(&chips) {
+        // Two unlabelled chips of the same type are told apart by their hwmon node
+        let chip = if chips.iter().filter(|other| *other == chip).count() > 1 {
+            format!("{} ({})", chip, hwmon)
+        } else {
+            chip.clone()
+        };
+        let mut found: Vec<(ChannelKind, String)> = fs.read_dir(&format!("{}/{}", HWMON, hwmon))
+            .iter()
+            .filter_map(|file| ChannelKind::parse(file).map(|(kind, channel)| (kind, channel.to_string())))
+            .collect();
+        // Group by kind, then by channel number so in10 comes after in9
+        found.sort_by_key(|(kind, channel)| {
+            (ChannelKind::ALL.iter().position(|other| other == kind), channel[kind.prefix().len()..].parse::<u32>().unwrap_or(0))
+        });
+
+        for (kind, channel) in found {
+            let name = match read(hwmon, &format!("{}_label", channel)) {
+                Some(label) => format!("{} {}", chip, label),
+                None => format!("{} {}", chip, channel),
+            };
+            channels.push(HwmonChannel { hwmon: hwmon.clone(), chip: chip.clone(), channel, kind, name });
+        }
+    }
+    channels
+}
+
+/// Temperature, voltage, current, power and fan channels of all hwmon devices.
+pub struct HwmonCollector {
+    channels: Vec<HwmonChannel>,
+}
+
+impl HwmonCollector {
+    pub fn discover(fs: &dyn SysFs) -> Option<HwmonCollector> {
+        let channels = discover_hwmon_channels(fs);
+        if channels.is_empty() {
+            return None;
+        }
+        Some(HwmonCollector { channels })
+    }
+}
+
+impl Collector for HwmonCollector {
+    fn name(&self) -> &str {
+        "Hwmon"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        for channel in &self.channels {
+            // Some channels fail to read while the chip is powered down; skip them for this interval
+            if let Some(value) = channel.read(fs) {
+                samples.push(Sample::new(channel.series(), value));
+            }
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        // One chart per channel type that is present, two per row
+        let charts: Vec<ChartSpec> = ChannelKind::ALL.iter()
+            .filter(|kind| self.channels.iter().any(|channel| channel.kind == **kind))
+            .map(|kind| ChartSpec::new(kind.title()).series(SeriesFilter::metric(kind.metric().0)))
+            .collect();
+        charts.chunks(2).map(|row| row.to_vec()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+
+    #[test]
+    fn parses_channel_files() {
+        assert_eq!(ChannelKind::parse("curr1_input"), Some((ChannelKind::Curr, "curr1")));
+        assert_eq!(ChannelKind::parse("in0_input"), Some((ChannelKind::In, "in0")));
+        assert_eq!(ChannelKind::parse("temp1_crit"), None);
+        assert_eq!(ChannelKind::parse("intrusion0_input"), None);
+    }
+
+    #[test]
+    fn reads_power_monitors() {
+        let fs = fixture("rk3588");
+        let channels = discover_hwmon_channels(&fs);
+        let rail: Vec<_> = channels.iter()
+            .filter(|channel| channel.chip == "vcc12v_dcin")
+            .map(|channel| (channel.name.as_str(), channel.read(&fs).unwrap()))
+            .collect();
+        assert_eq!(rail, [
+            ("vcc12v_dcin in0", 0.002),
+            ("vcc12v_dcin in1", 12.104),
+            ("vcc12v_dcin curr1", 1.25),
+            ("vcc12v_dcin power1", 15.125),
+        ]);
+        assert_eq!(channels[0].series().key(), r#"hwmon_temperature{hwmon="hwmon0",chip="soc_thermal",channel="temp1"}"#);
+    }
+
+    #[test]
+    fn uses_channel_labels() {
+        let fs = fixture("rk3566");
+        let fans: Vec<_> = discover_hwmon_channels(&fs).into_iter()
+            .filter(|channel| channel.kind == ChannelKind::Fan)
+            .map(|channel| channel.name)
+            .collect();
+        assert_eq!(fans, ["pwmfan fan1", "emc2302 case", "emc2302 nvme"]);
+
+        let layout = HwmonCollector::discover(&fs).unwrap().layout();
+        let titles: Vec<_> = layout.iter().flatten().map(|chart| chart.title.as_str()).collect();
+        assert_eq!(titles, ["Temperature (°C)", "Fan Speed (RPM)"]);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::devfreq::{DevfreqDevice, DevfreqKind};
+
+/// `/proc/meminfo` fields in bytes, or as plain counts for the `HugePages_*`
+/// lines that have no unit.
+struct Meminfo(Vec<(String, u64)>);
+
+impl Meminfo {
+    fn read(fs: &dyn SysFs) -> Meminfo {
+        let fields = fs.read_to_string("/proc/meminfo")
+            .map(|content| content.lines()
+                .filter_map(|line| {
+                    let (key, rest) = line.split_once(':')?;
+                    let mut parts = rest.split_whitespace();
+                    let value = parts.next()?.parse::<u64>().ok()?;
+                    let scale = if parts.next() == Some("kB") { 1024 } else { 1 };
+                    Some((key.to_string(), value * scale))
+                })
+                .collect())
+            .unwrap_or_default();
+        Meminfo(fields)
+    }
+
+    fn get(&self, key: &str) -> u64 {
+        self.0.iter().find(|(field, _)| field == key).map_or(0, |(_, value)| *value)
+    }
+
+    /// Memory not given back on demand: everything but free memory, buffers,
+    /// page cache and reclaimable slab, as `free` and `htop` count it.
+    fn used(&self) -> u64 {
+        self.get("MemTotal")
+            .saturating_sub(self.get("MemFree"))
+            .saturating_sub(self.get("Buffers"))
+            .saturating_sub(self.get("Cached"))
+            .saturating_sub(self.get("SReclaimable"))
+    }
+}
+
+fn get_memory_info(meminfo: &Meminfo) -> (f64, f64) {
+    let mem_total = meminfo.get("MemTotal");
+    let mem_available = meminfo.get("MemAvailable");
+    let swap_total = meminfo.get("SwapTotal");
+    let swap_free = meminfo.get("SwapFree");
+
+    let memory_usage = if mem_total > 0 {
+        (mem_total.saturating_sub(mem_available) as f64 / mem_total as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let swap_usage = if swap_total > 0 {
+        (swap_total.saturating_sub(swap_free) as f64 / swap_total as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    (memory_usage, swap_usage)
+}
+
+/// `/proc/meminfo` fields emitted as `meminfo{field=...}` series.
+const MEMINFO_FIELDS: [&str; 17] = [
+    "MemTotal", "MemFree", "MemAvailable", "Buffers", "Cached", "SwapCached", "Shmem", "Slab",
+    "SReclaimable", "SUnreclaim", "Dirty", "Writeback", "AnonPages", "Mapped", "CmaTotal", "CmaFree", "Hugetlb",
+];
+
+/// Huge page pool counters, emitted as `hugepages{field=...}` series.
+const HUGEPAGE_FIELDS: [&str; 4] = ["HugePages_Total", "HugePages_Free", "HugePages_Rsvd", "HugePages_Surp"];
+
+/// Columns of `/sys/block/zramN/mm_stat` that are emitted, in bytes.
+const ZRAM_FIELDS: [(usize, &str, &str); 3] = [
+    (0, "zram_original", "original"),
+    (1, "zram_compressed", "compressed"),
+    (2, "zram_used", "used"),
+];
+
+/// RAM, swap, CMA, huge page and zram usage from /proc/meminfo and
+/// /sys/block, with the DDR controller's devfreq node where the SoC has one.
+pub struct MemoryCollector {
+    dmc: Option<DevfreqDevice>,
+    /// `zramN` block devices.
+    zram: Vec<String>,
+}
+
+impl MemoryCollector {
+    pub fn new(fs: &dyn SysFs, devices: &[DevfreqDevice]) -> MemoryCollector {
+        MemoryCollector {
+            dmc: devices.iter().find(|device| device.kind == DevfreqKind::Dmc).cloned(),
+            zram: fs.read_dir("/sys/block").into_iter().filter(|device| device.starts_with("zram")).collect(),
+        }
+    }
+}
+
+impl Collector for MemoryCollector {
+    fn name(&self) -> &str {
+        "Memory"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let meminfo = Meminfo::read(fs);
+        let (memory_usage, swap_usage) = get_memory_info(&meminfo);
+        samples.push(Sample::new(Series::new("memory_usage", Unit::Percent).name("Memory Usage"), memory_usage));
+        samples.push(Sample::new(Series::new("swap_usage", Unit::Percent).name("Swap Usage"), swap_usage));
+
+        samples.push(Sample::new(Series::new("memory_used", Unit::Byte).name("Used"), meminfo.used() as f64));
+        let swap_used = meminfo.get("SwapTotal").saturating_sub(meminfo.get("SwapFree"));
+        samples.push(Sample::new(Series::new("swap_used", Unit::Byte).name("Swap Used"), swap_used as f64));
+        for field in MEMINFO_FIELDS {
+            samples.push(Sample::new(Series::new("meminfo", Unit::Byte).label("field", field).name(field), meminfo.get(field) as f64));
+        }
+        for field in HUGEPAGE_FIELDS {
+            samples.push(Sample::new(Series::new("hugepages", Unit::Count).label("field", field).name(field), meminfo.get(field) as f64));
+        }
+
+        for device in &self.zram {
+            let Some(mm_stat) = fs.read_to_string(&format!("/sys/block/{}/mm_stat", device)) else {
+                continue;
+            };
+            let columns: Vec<u64> = mm_stat.split_whitespace().filter_map(|column| column.parse().ok()).collect();
+            for (column, metric, name) in ZRAM_FIELDS {
+                let value = columns.get(column).copied().unwrap_or(0);
+                samples.push(Sample::new(
+                    Series::new(metric, Unit::Byte).label("device", device).name(format!("{} {}", device, name)),
+                    value as f64,
+                ));
+            }
+        }
+
+        if let Some(dmc) = &self.dmc {
+            let (load, freq) = dmc.read(fs);
+            let governor = dmc.governor(fs).unwrap_or_else(|| "unknown".to_string());
+            samples.push(Sample::new(Series::new("dmc_load", Unit::Percent).name(format!("DMC Load ({})", governor)), load));
+            samples.push(Sample::new(
+                Series::new("dmc_frequency", Unit::Hertz).name(format!("DMC Frequency ({})", governor)),
+                freq as f64,
+            ));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let field = |field: &str| SeriesFilter::metric("meminfo").label("field", field);
+        // Used is what the other layers leave of MemTotal, so together they add up to it
+        let mut top = vec![ChartSpec::new("Memory (MiB)")
+            .series(SeriesFilter::metric("memory_used"))
+            .series(field("Buffers"))
+            .series(field("Cached"))
+            .series(field("SReclaimable"))
+            .series(field("MemFree"))
+            .stacked()];
+        let mut bottom = vec![
+            ChartSpec::new("Swap and zram (MiB)")
+                .series(SeriesFilter::metric("swap_used"))
+                .series(SeriesFilter::metric("zram_original"))
+                .series(SeriesFilter::metric("zram_compressed"))
+                .series(SeriesFilter::metric("zram_used")),
+            ChartSpec::new("Huge Pages").series(SeriesFilter::metric("hugepages")),
+        ];
+        if self.dmc.is_some() {
+            top.push(ChartSpec::new("DMC Load (%)").series(SeriesFilter::metric("dmc_load")).y_bounds(0.0, 100.0));
+            bottom.push(ChartSpec::new("DMC Frequency (MHz)").series(SeriesFilter::metric("dmc_frequency")));
+        }
+
+        vec![
+            top,
+            vec![
+                ChartSpec::new("Kernel and Dirty (MiB)")
+                    .series(field("Shmem"))
+                    .series(field("Slab"))
+                    .series(field("Dirty"))
+                    .series(field("Writeback")),
+                ChartSpec::new("CMA (MiB)").series(field("CmaTotal")).series(field("CmaFree")),
+            ],
+            bottom,
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        let field = |field: &str| SeriesFilter::metric("meminfo").label("field", field);
+        vec![
+            SeriesFilter::metric("memory_used"),
+            field("MemAvailable"),
+            field("Cached"),
+            field("CmaFree"),
+            SeriesFilter::metric("swap_used"),
+            SeriesFilter::metric("dmc_load"),
+            SeriesFilter::metric("dmc_frequency"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, value};
+    use crate::collectors::devfreq::discover_devfreq_devices;
+
+    #[test]
+    fn computes_usage_from_available() {
+        let (memory, swap) = get_memory_info(&Meminfo::read(&fixture("rk3566")));
+        assert!((memory - (4019384.0 - 3102440.0) / 4019384.0 * 100.0).abs() < 1e-9);
+        assert!((swap - 200000.0 / 2009688.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_swap_is_zero() {
+        assert_eq!(get_memory_info(&Meminfo::read(&fixture("rk3528"))).1, 0.0);
+    }
+
+    #[test]
+    fn more_available_than_total_is_zero() {
+        let meminfo = Meminfo(vec![("MemTotal".to_string(), 1000), ("MemAvailable".to_string(), 2000)]);
+        assert_eq!(get_memory_info(&meminfo).0, 0.0);
+    }
+
+    #[test]
+    fn reads_the_ddr_controller() {
+        let fs = fixture("rk3588");
+        let mut collector = MemoryCollector::new(&fs, &discover_devfreq_devices(&fs));
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+
+        let dmc = samples.iter().find(|sample| sample.series.metric == "dmc_frequency").unwrap();
+        assert_eq!(dmc.value, 2_112_000_000.0);
+        assert_eq!(dmc.series.name, "DMC Frequency (dmc_ondemand)");
+        assert_eq!(samples.iter().find(|sample| sample.series.metric == "dmc_load").unwrap().value, 35.0);
+        assert_eq!(MemoryCollector::new(&fs, &[]).layout()[0].len(), 1);
+    }
+
+    #[test]
+    fn breaks_down_meminfo_and_zram() {
+        let fs = fixture("rk3588");
+        let mut collector = MemoryCollector::new(&fs, &[]);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+
+        assert_eq!(value(&samples, r#"meminfo{field="CmaFree"}"#), 63488.0 * 1024.0);
+        assert_eq!(value(&samples, r#"hugepages{field="HugePages_Total"}"#), 0.0);
+        assert_eq!(value(&samples, "memory_used"), (16166548.0 - 12544332.0 - 41232.0 - 1684880.0 - 41200.0) * 1024.0);
+        // The stacked layers of the memory chart add up to MemTotal
+        let field = |field: &str| value(&samples, &format!("meminfo{{field=\"{}\"}}", field));
+        let layers = value(&samples, "memory_used") + field("Buffers") + field("Cached") + field("SReclaimable") + field("MemFree");
+        assert_eq!(layers, field("MemTotal"));
+        assert_eq!(value(&samples, r#"zram_compressed{device="zram0"}"#), 134217728.0);
+        assert_eq!(Unit::Byte.format(value(&samples, r#"zram_original{device="zram0"}"#)), "512.0 MiB");
+    }
+}
@@ -0,0 +1,187 @@
+//! Built-in collectors. A board-specific sensor only needs a `Collector`
+//! implementation and a `register` call in `default_registry`.
+
+mod clk;
+mod container;
+mod cooling;
+mod cpu;
+mod devfreq;
+mod disk;
+mod hwmon;
+mod memory;
+mod network;
+mod npu;
+mod power;
+mod pressure;
+mod process;
+mod rga;
+mod thermal;
+mod video;
+
+use crate::collector::Registry;
+use crate::sysfs::SysFs;
+use clk::{ClockCollector, ClockTree};
+use container::ContainerCollector;
+use cooling::CoolingCollector;
+use cpu::CpuCollector;
+use devfreq::{DevfreqCollector, GpuCollector, discover_devfreq_devices};
+use disk::DiskCollector;
+use hwmon::HwmonCollector;
+use memory::MemoryCollector;
+use network::NetworkCollector;
+use npu::NpuCollector;
+use power::PowerCollector;
+use pressure::PressureCollector;
+use process::ProcessCollector;
+use rga::RgaCollector;
+use thermal::ThermalCollector;
+use video::VideoCollector;
+
+pub fn get_device_info(fs: &dyn SysFs) -> String {
+    if let Some(content) = fs.read_to_string("/sys/firmware/devicetree/base/compatible") {
+        return content.trim_end_matches('\0').replace('\0', ", ");
+    }
+    "Unknown".to_string()
+}
+
+/// Registers a collector for every block found on this board.
+pub fn default_registry(fs: Box<dyn SysFs>) -> Registry {
+    let devfreq = discover_devfreq_devices(&*fs);
+    let clock_tree = ClockTree::new(&*fs);
+    let cpu = CpuCollector::new(&*fs);
+    let gpu = GpuCollector::discover(&devfreq);
+    let npu = NpuCollector::discover(&*fs, &devfreq);
+    let rga = RgaCollector::discover(&*fs, &clock_tree);
+    let video = VideoCollector::discover(&*fs, &devfreq, &clock_tree);
+    let clocks = ClockCollector::discover(&clock_tree);
+    let memory = MemoryCollector::new(&*fs, &devfreq);
+    let processes = ProcessCollector::new(&*fs);
+    let network = NetworkCollector::discover(&*fs);
+    let disk = DiskCollector::discover(&*fs);
+    let pressure = PressureCollector::discover(&*fs);
+    let containers = ContainerCollector::discover(&*fs);
+    let thermal = ThermalCollector::new(&*fs);
+    let cooling = CoolingCollector::new(&*fs);
+    let hwmon = HwmonCollector::discover(&*fs);
+    let power = PowerCollector::discover(&*fs, npu.is_some());
+    let mut registry = Registry::new(fs);
+    if !clock_tree.clocks().is_empty() {
+        registry.before_collect(move |fs| clock_tree.refresh(fs));
+    }
+
+    registry.register(cpu);
+    if let Some(gpu) = gpu {
+        registry.register(gpu);
+    }
+    if let Some(npu) = npu {
+        registry.register(npu);
+    }
+    if let Some(rga) = rga {
+        registry.register(rga);
+    }
+    if let Some(video) = video {
+        registry.register(video);
+    }
+    registry.register(memory);
+    registry.register(processes);
+    if let Some(network) = network {
+        registry.register(network);
+    }
+    if let Some(disk) = disk {
+        registry.register(disk);
+    }
+    if let Some(pressure) = pressure {
+        registry.register(pressure);
+    }
+    if let Some(containers) = containers {
+        registry.register(containers);
+    }
+    registry.register(thermal);
+    registry.register(cooling);
+    if let Some(hwmon) = hwmon {
+        registry.register(hwmon);
+    }
+    if let Some(power) = power {
+        registry.register(power);
+    }
+    if !devfreq.is_empty() {
+        registry.register(DevfreqCollector::new(devfreq));
+    }
+    if let Some(clocks) = clocks {
+        registry.register(clocks);
+    }
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use clk::CLK_SUMMARY;
+    use crate::sysfs::{FsSpace, RootFs, fixture};
+
+    #[test]
+    fn joins_compatible_strings() {
+        assert_eq!(get_device_info(&fixture("rk3588")), "radxa,rock-5b, rockchip,rk3588");
+        assert_eq!(get_device_info(&fixture("rk3528")), "armsom,sige1, rockchip,rk3528a");
+    }
+
+    #[test]
+    fn registers_tabs_per_board() {
+        let tabs = |board| {
+            let registry = default_registry(Box::new(fixture(board)));
+            registry.collectors().iter().map(|collector| collector.name().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(tabs("rk3588"), ["CPU", "GPU", "NPU", "RGA", "Video", "Memory", "Processes", "Network", "Disk", "Pressure", "Containers", "Thermal", "Cooling", "Hwmon", "Power", "Devfreq", "Clocks"]);
+        assert_eq!(tabs("rk3528"), ["CPU", "GPU", "RGA", "Memory", "Processes", "Thermal", "Cooling", "Hwmon", "Devfreq", "Clocks"]);
+    }
+
+    /// Counts reads of clk_summary on the way to the fixture.
+    struct CountingFs {
+        fs: RootFs,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl SysFs for CountingFs {
+        fn read_to_string(&self, path: &str) -> Option<String> {
+            if path == CLK_SUMMARY {
+                self.reads.set(self.reads.get() + 1);
+            }
+            self.fs.read_to_string(path)
+        }
+
+        fn read_dir(&self, path: &str) -> Vec<String> {
+            self.fs.read_dir(path)
+        }
+
+        fn read_link(&self, path: &str) -> Option<String> {
+            self.fs.read_link(path)
+        }
+
+        fn statvfs(&self, path: &str) -> Option<FsSpace> {
+            self.fs.statvfs(path)
+        }
+    }
+
+    #[test]
+    fn reads_clk_summary_once_per_interval() {
+        let reads = Rc::new(Cell::new(0));
+        let mut registry = default_registry(Box::new(CountingFs { fs: fixture("rk3588"), reads: reads.clone() }));
+        reads.set(0);
+        let metrics = registry.collect();
+        assert_eq!(reads.get(), 1);
+        // Video and RGA both take their rates from the shared tree
+        assert_eq!(metrics.get(r#"video_clock{clock="clk_rkvdec0_core"}"#).unwrap().value, 600_000_000.0);
+        assert_eq!(metrics.get(r#"rga_frequency{clock="aclk_rga2"}"#).unwrap().value, 800_000_000.0);
+    }
+
+    #[test]
+    fn collects_from_fixture() {
+        let mut registry = default_registry(Box::new(fixture("rk3588")));
+        let metrics = registry.collect();
+        let sample = metrics.get("cpu_cluster_frequency{cluster=\"policy4\"}").unwrap();
+        assert_eq!(sample.value, 2_352_000_000.0);
+        assert_eq!(sample.series.source, "CPU");
+    }
+}
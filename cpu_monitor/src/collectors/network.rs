@@ -0,0 +1,221 @@
+use std::time::Instant;
+
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+/// Cumulative counters of one interface.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+struct NetCounters {
+    rx_bytes: u64,
+    rx_packets: u64,
+    rx_errors: u64,
+    rx_dropped: u64,
+    tx_bytes: u64,
+    tx_packets: u64,
+    tx_errors: u64,
+    tx_dropped: u64,
+}
+
+/// Parses `/proc/net/dev`, whose rows are `iface: <8 receive> <8 transmit>`
+/// counters below two header lines.
+fn parse_net_dev(content: &str) -> Vec<(String, NetCounters)> {
+    content.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<u64> = rest.split_whitespace().filter_map(|field| field.parse().ok()).collect();
+            if fields.len() < 16 {
+                return None;
+            }
+            Some((name.trim().to_string(), NetCounters {
+                rx_bytes: fields[0],
+                rx_packets: fields[1],
+                rx_errors: fields[2],
+                rx_dropped: fields[3],
+                tx_bytes: fields[8],
+                tx_packets: fields[9],
+                tx_errors: fields[10],
+                tx_dropped: fields[11],
+            }))
+        })
+        .collect()
+}
+
+/// Counters of every interface from `/proc/net/dev`, or from
+/// `/sys/class/net/*/statistics` where procfs doesn't have them.
+fn read_counters(fs: &dyn SysFs) -> Vec<(String, NetCounters)> {
+    if let Some(content) = fs.read_to_string("/proc/net/dev") {
+        return parse_net_dev(&content);
+    }
+    fs.read_dir("/sys/class/net")
+        .into_iter()
+        .filter_map(|interface| {
+            let counter = |name: &str| fs.read_to_string(&format!("/sys/class/net/{}/statistics/{}", interface, name))
+                .and_then(|value| value.trim().parse().ok());
+            let counters = NetCounters {
+                rx_bytes: counter("rx_bytes")?,
+                rx_packets: counter("rx_packets")?,
+                rx_errors: counter("rx_errors").unwrap_or(0),
+                rx_dropped: counter("rx_dropped").unwrap_or(0),
+                tx_bytes: counter("tx_bytes")?,
+                tx_packets: counter("tx_packets")?,
+                tx_errors: counter("tx_errors").unwrap_or(0),
+                tx_dropped: counter("tx_dropped").unwrap_or(0),
+            };
+            Some((interface, counters))
+        })
+        .collect()
+}
+
+/// Operational state and link speed for the legend, e.g. `up, 1000 Mb/s`.
+/// `speed` reads -1 or fails while the link is down, and on most Wi-Fi drivers.
+fn describe_link(fs: &dyn SysFs, interface: &str) -> String {
+    let operstate = fs.read_to_string(&format!("/sys/class/net/{}/operstate", interface))
+        .map(|state| state.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let speed = fs.read_to_string(&format!("/sys/class/net/{}/speed", interface))
+        .and_then(|speed| speed.trim().parse::<i64>().ok())
+        .filter(|speed| *speed > 0);
+    match speed {
+        Some(speed) => format!("{}, {} Mb/s", operstate, speed),
+        None => operstate,
+    }
+}
+
+/// Per-interface throughput, packet, error and drop rates. Interfaces are
+/// read every interval, so USB adapters and container veths come and go.
+pub struct NetworkCollector {
+    last: Option<(Instant, Vec<(String, NetCounters)>)>,
+}
+
+impl NetworkCollector {
+    /// `None` on a board with no interface but loopback.
+    pub fn discover(fs: &dyn SysFs) -> Option<NetworkCollector> {
+        if read_counters(fs).iter().all(|(interface, _)| interface == "lo") {
+            return None;
+        }
+        Some(NetworkCollector { last: None })
+    }
+
+    fn collect_at(&mut self, fs: &dyn SysFs, now: Instant, samples: &mut Vec<Sample>) {
+        let counters: Vec<(String, NetCounters)> = read_counters(fs).into_iter().filter(|(interface, _)| interface != "lo").collect();
+        if let Some((last_time, last)) = &self.last {
+            let elapsed = now.duration_since(*last_time).as_secs_f64();
+            for (interface, counters) in counters.iter().filter(|_| elapsed > 0.0) {
+                let Some((_, before)) = last.iter().find(|(other, _)| other == interface) else {
+                    continue;
+                };
+                // A counter that went backwards was reset, e.g. by a driver reload
+                let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / elapsed;
+                let link = describe_link(fs, interface);
+                let series = |metric, unit, name: String| Series::new(metric, unit).label("interface", interface).name(name);
+                samples.push(Sample::new(
+                    series("net_rx", Unit::ByteRate, format!("{} rx ({})", interface, link)),
+                    rate(counters.rx_bytes, before.rx_bytes),
+                ));
+                samples.push(Sample::new(
+                    series("net_tx", Unit::ByteRate, format!("{} tx ({})", interface, link)),
+                    rate(counters.tx_bytes, before.tx_bytes),
+                ));
+                for (metric, suffix, now, before) in [
+                    ("net_rx_packets", "rx", counters.rx_packets, before.rx_packets),
+                    ("net_tx_packets", "tx", counters.tx_packets, before.tx_packets),
+                    ("net_rx_errors", "rx errors", counters.rx_errors, before.rx_errors),
+                    ("net_tx_errors", "tx errors", counters.tx_errors, before.tx_errors),
+                    ("net_rx_drops", "rx drops", counters.rx_dropped, before.rx_dropped),
+                    ("net_tx_drops", "tx drops", counters.tx_dropped, before.tx_dropped),
+                ] {
+                    samples.push(Sample::new(
+                        series(metric, Unit::PerSecond, format!("{} {}", interface, suffix)),
+                        rate(now, before),
+                    ));
+                }
+            }
+        }
+        self.last = Some((now, counters));
+    }
+}
+
+impl Collector for NetworkCollector {
+    fn name(&self) -> &str {
+        "Network"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        self.collect_at(fs, Instant::now(), samples);
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        vec![
+            vec![
+                ChartSpec::new("Receive (MiB/s)").series(SeriesFilter::metric("net_rx")),
+                ChartSpec::new("Transmit (MiB/s)").series(SeriesFilter::metric("net_tx")),
+                // Camera streams go through RGA and the encoders before they are sent
+                ChartSpec::new("RGA and Encoder Load (%)")
+                    .series(SeriesFilter::metric("rga_usage"))
+                    .series(SeriesFilter::metric("video_load").label("block", "RKVENC"))
+                    .y_bounds(0.0, 100.0),
+            ],
+            vec![
+                ChartSpec::new("Packets (/s)")
+                    .series(SeriesFilter::metric("net_rx_packets"))
+                    .series(SeriesFilter::metric("net_tx_packets")),
+                ChartSpec::new("Errors and Drops (/s)")
+                    .series(SeriesFilter::metric("net_rx_errors"))
+                    .series(SeriesFilter::metric("net_tx_errors"))
+                    .series(SeriesFilter::metric("net_rx_drops"))
+                    .series(SeriesFilter::metric("net_tx_drops")),
+            ],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("net_rx"), SeriesFilter::metric("net_tx")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::sysfs::{fixture, rewound, sample};
+
+    #[test]
+    fn reads_procfs_and_sysfs_counters() {
+        let counters = read_counters(&fixture("rk3588"));
+        let names: Vec<_> = counters.iter().map(|(interface, _)| interface.as_str()).collect();
+        assert_eq!(names, ["lo", "eth0", "wlan0"]);
+        assert_eq!((counters[1].1.rx_bytes, counters[1].1.rx_dropped, counters[1].1.tx_packets), (1834201734, 12, 6512093));
+
+        // No /proc/net/dev in this capture, only sysfs statistics
+        let counters = read_counters(&fixture("rk3566"));
+        assert_eq!(counters[0].0, "eth0");
+        assert_eq!((counters[0].1.rx_bytes, counters[0].1.rx_dropped), (5520113, 4));
+
+        assert!(NetworkCollector::discover(&fixture("rk3528")).is_none());
+    }
+
+    #[test]
+    fn computes_rates() {
+        let fs = fixture("rk3588");
+        let mut collector = NetworkCollector::discover(&fs).unwrap();
+        let read = |collector: &mut NetworkCollector, now| {
+            let mut samples = Vec::new();
+            collector.collect_at(&fs, now, &mut samples);
+            samples
+        };
+        let (first, samples) = rewound(&mut collector, Duration::from_secs(2), read, |collector| {
+            // eth0 had received 4 MiB and 3000 packets less
+            let (_, last) = collector.last.as_mut().unwrap();
+            last[0].1.rx_bytes -= 4 * 1_048_576;
+            last[0].1.rx_packets -= 3000;
+        });
+        assert!(first.is_empty());
+
+        assert_eq!(Unit::ByteRate.format(sample(&samples, r#"net_rx{interface="eth0"}"#).value), "2.00 MiB/s");
+        assert_eq!(sample(&samples, r#"net_rx{interface="eth0"}"#).series.name, "eth0 rx (up, 1000 Mb/s)");
+        assert_eq!(sample(&samples, r#"net_tx{interface="wlan0"}"#).series.name, "wlan0 tx (dormant)");
+        assert_eq!(sample(&samples, r#"net_rx_packets{interface="eth0"}"#).value, 1500.0);
+        assert_eq!(sample(&samples, r#"net_tx_drops{interface="eth0"}"#).value, 0.0);
+    }
+}
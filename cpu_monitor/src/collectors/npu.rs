@@ -0,0 +1,114 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::devfreq::{DevfreqDevice, DevfreqKind};
+
+const RKNPU_LOAD: &str = "/sys/kernel/debug/rknpu/load";
+
+/// Parses the rknpu debugfs load, one value per core. Single-core NPUs print
+/// "NPU load:  0%", multi-core ones "NPU load:  Core0:  0%, Core1:  0%, Core2:  0%,".
+fn parse_npu_load(content: &str) -> Vec<f64> {
+    let parse_percent = |text: &str| text.split('%').next().and_then(|value| value.trim().parse::<f64>().ok());
+    let mut loads = Vec::new();
+
+    for line in content.lines() {
+        let Some((_, load_str)) = line.split_once("NPU load:") else {
+            continue;
+        };
+        if load_str.contains("Core") {
+            for core in load_str.split(',') {
+                if let Some((_, percent_str)) = core.split_once(':')
+                    && let Some(load) = parse_percent(percent_str)
+                {
+                    loads.push(load);
+                }
+            }
+        } else if let Some(load) = parse_percent(load_str) {
+            loads.push(load);
+        }
+    }
+    loads
+}
+
+/// Per-core and aggregate NPU load plus the clock of the `*.npu` devfreq node.
+pub struct NpuCollector {
+    device: Option<DevfreqDevice>,
+}
+
+impl NpuCollector {
+    pub fn discover(fs: &dyn SysFs, devices: &[DevfreqDevice]) -> Option<NpuCollector> {
+        let device = devices.iter().find(|device| device.kind == DevfreqKind::Npu).cloned();
+        if device.is_none() && fs.read_to_string(RKNPU_LOAD).is_none() {
+            return None;
+        }
+        Some(NpuCollector { device })
+    }
+}
+
+impl Collector for NpuCollector {
+    fn name(&self) -> &str {
+        "NPU"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let cores = fs.read_to_string(RKNPU_LOAD)
+            .map(|content| parse_npu_load(&content))
+            .unwrap_or_default();
+
+        // Aggregate load is the mean across cores
+        let aggregate = if cores.is_empty() { 0.0 } else { cores.iter().sum::<f64>() / cores.len() as f64 };
+        samples.push(Sample::new(Series::new("npu_usage", Unit::Percent).name("Aggregate"), aggregate));
+        for (core, load) in cores.iter().enumerate() {
+            samples.push(Sample::new(
+                Series::new("npu_core_usage", Unit::Percent).label("core", core).name(format!("Core{}", core)),
+                *load,
+            ));
+        }
+
+        let freq = self.device.as_ref().map(|device| device.read(fs).1).unwrap_or(0);
+        samples.push(Sample::new(Series::new("npu_frequency", Unit::Hertz).name("Frequency"), freq as f64));
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        // Aggregate plus one line per core, so work pinned to a single core stands out
+        vec![
+            vec![ChartSpec::new("NPU Usage (%)")
+                .series(SeriesFilter::metric("npu_usage"))
+                .series(SeriesFilter::metric("npu_core_usage"))
+                .y_bounds(0.0, 100.0)],
+            vec![ChartSpec::new("NPU Frequency (MHz)").series(SeriesFilter::metric("npu_frequency"))],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::devfreq::discover_devfreq_devices;
+    use crate::sysfs::{fixture, value};
+
+    #[test]
+    fn parses_single_and_multi_core_load() {
+        assert_eq!(parse_npu_load("NPU load:  25%\n"), vec![25.0]);
+        assert_eq!(parse_npu_load("NPU load:  Core0: 35%, Core1:  0%, Core2: 12%,\n"), vec![35.0, 0.0, 12.0]);
+        assert!(parse_npu_load("").is_empty());
+    }
+
+    #[test]
+    fn reports_every_core_and_the_mean() {
+        let fs = fixture("rk3588");
+        let mut collector = NpuCollector::discover(&fs, &discover_devfreq_devices(&fs)).unwrap();
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+
+        assert_eq!(value(&samples, "npu_core_usage{core=\"2\"}"), 12.0);
+        assert!((value(&samples, "npu_usage") - 47.0 / 3.0).abs() < 1e-9);
+        assert_eq!(value(&samples, "npu_frequency"), 1e9);
+    }
+
+    #[test]
+    fn absent_without_npu() {
+        let fs = fixture("rk3528");
+        assert!(NpuCollector::discover(&fs, &discover_devfreq_devices(&fs)).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Instant;
+
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Kind, Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::hwmon::{ChannelKind, HwmonChannel, discover_hwmon_channels};
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// Where a rail's power reading comes from.
+#[derive(Debug, Clone)]
+enum RailSource {
+    /// A hwmon `powerN_input`, e.g. an INA226 on the DC input.
+    Hwmon(HwmonChannel),
+    /// A `power_supply` node, e.g. the USB-PD sink or a battery.
+    PowerSupply(String),
+}
+
+/// A power rail and the energy drawn through it since the session started.
+#[derive(Debug, Clone)]
+struct Rail {
+    name: String,
+    source: RailSource,
+    /// Joules integrated so far.
+    energy: f64,
+    /// Time and power of the previous reading.
+    last: Option<(Instant, f64)>,
+}
+
+impl Rail {
+    fn new(name: String, source: RailSource) -> Rail {
+        Rail { name, source, energy: 0.0, last: None }
+    }
+
+    /// Instantaneous power in watts.
+    fn read(&self, fs: &dyn SysFs) -> Option<f64> {
+        match &self.source {
+            RailSource::Hwmon(channel) => channel.read(fs),
+            RailSource::PowerSupply(supply) => {
+                let read = |attribute| fs.read_to_string(&format!("{}/{}/{}", POWER_SUPPLY, supply, attribute))
+                    .and_then(|content| content.trim().parse::<f64>().ok());
+                // power_now is in µW; most chargers only report µV and µA
+                match read("power_now") {
+                    Some(microwatts) => Some(microwatts / 1_000_000.0),
+                    None => Some(read("voltage_now")? * read("current_now")? / 1e12),
+                }
+            }
+        }
+    }
+
+    /// Adds the energy since the previous reading, using the trapezoid rule,
+    /// and returns the average power over that interval.
+    fn integrate(&mut self, now: Instant, power: f64) -> f64 {
+        let average = match self.last {
+            Some((then, previous)) => {
+                let seconds = now.duration_since(then).as_secs_f64();
+                let joules = (previous + power) / 2.0 * seconds;
+                self.energy += joules;
+                if seconds > 0.0 { joules / seconds } else { power }
+            }
+            None => power,
+        };
+        self.last = Some((now, power));
+        average
+    }
+}
+
+fn discover_rails(fs: &dyn SysFs) -> Vec<Rail> {
+    let channels: Vec<HwmonChannel> = discover_hwmon_channels(fs)
+        .into_iter()
+        .filter(|channel| channel.kind == ChannelKind::Power)
+        .collect();
+    let mut rails: Vec<Rail> = channels.iter()
+        .map(|channel| {
+            // A monitor with a single power channel is named after its rail label alone
+            let siblings = channels.iter().filter(|other| other.hwmon == channel.hwmon).count();
+            let name = if siblings == 1 { channel.chip.clone() } else { channel.name.clone() };
+            Rail::new(name, RailSource::Hwmon(channel.clone()))
+        })
+        .collect();
+
+    for supply in fs.read_dir(POWER_SUPPLY) {
+        let rail = Rail::new(supply.clone(), RailSource::PowerSupply(supply));
+        if rail.read(fs).is_some() {
+            rails.push(rail);
+        }
+    }
+    rails
+}
+
+/// Instantaneous and average power of every hwmon and `power_supply` rail,
+/// with the energy drawn over the session.
+pub struct PowerCollector {
+    rails: Vec<Rail>,
+    /// Chart NPU load next to energy, to compare inference efficiency.
+    npu: bool,
+}
+
+impl PowerCollector {
+    pub fn discover(fs: &dyn SysFs, npu: bool) -> Option<PowerCollector> {
+        let rails = discover_rails(fs);
+        if rails.is_empty() {
+            return None;
+        }
+        Some(PowerCollector { rails, npu })
+    }
+
+    fn collect_at(&mut self, fs: &dyn SysFs, now: Instant, samples: &mut Vec<Sample>) {
+        for rail in &mut self.rails {
+            let Some(power) = rail.read(fs) else {
+                continue;
+            };
+            let average = rail.integrate(now, power);
+            let series = |metric, unit| Series::new(metric, unit).label("rail", &rail.name).name(&rail.name);
+            samples.push(Sample::new(series("power", Unit::Watt), power));
+            samples.push(Sample::new(series("power_average", Unit::Watt), average));
+            samples.push(Sample::new(series("energy", Unit::Joule).kind(Kind::Counter), rail.energy));
+        }
+    }
+}
+
+impl Collector for PowerCollector {
+    fn name(&self) -> &str {
+        "Power"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        self.collect_at(fs, Instant::now(), samples);
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let mut energy_row = vec![ChartSpec::new("Energy (Wh)").series(SeriesFilter::metric("energy"))];
+        if self.npu {
+            energy_row.push(ChartSpec::new("NPU Load (%)").series(SeriesFilter::metric("npu_usage")).y_bounds(0.0, 100.0));
+        }
+        vec![
+            vec![
+                ChartSpec::new("Power (W)").series(SeriesFilter::metric("power")),
+                ChartSpec::new("Average Power per Interval (W)").series(SeriesFilter::metric("power_average")),
+            ],
+            energy_row,
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("power"), SeriesFilter::metric("energy")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::sysfs::{fixture, value};
+
+    #[test]
+    fn finds_hwmon_and_power_supply_rails() {
+        let fs = fixture("rk3588");
+        let rails = discover_rails(&fs);
+        let rails: Vec<_> = rails.iter().map(|rail| (rail.name.as_str(), rail.read(&fs).unwrap())).collect();
+        assert_eq!(rails, [("vcc12v_dcin", 15.125), ("vcc5v0_sys", 12.625), ("tcpm-source-psy-6-0022", 30.0)]);
+
+        assert!(PowerCollector::discover(&fixture("rk3566"), true).is_none());
+    }
+
+    #[test]
+    fn integrates_energy() {
+        let fs = fixture("rk3588");
+        let mut collector = PowerCollector::discover(&fs, true).unwrap();
+        let start = Instant::now();
+        let mut samples = Vec::new();
+        collector.collect_at(&fs, start, &mut samples);
+        samples.clear();
+        collector.collect_at(&fs, start + Duration::from_secs(2), &mut samples);
+
+        assert_eq!(value(&samples, r#"energy{rail="vcc12v_dcin"}"#), 30.25);
+        assert_eq!(value(&samples, r#"power_average{rail="tcpm-source-psy-6-0022"}"#), 30.0);
+        assert_eq!(Unit::Joule.format(value(&samples, r#"energy{rail="tcpm-source-psy-6-0022"}"#)), "0.0167 Wh");
+    }
+}
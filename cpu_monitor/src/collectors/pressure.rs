@@ -0,0 +1,143 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Kind, Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+/// Resources with a file under `/proc/pressure`.
+const RESOURCES: [&str; 3] = ["cpu", "memory", "io"];
+
+/// One line of a PSI file. `some` is the share of time at least one task
+/// was stalled on the resource, `full` the share all non-idle tasks were.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pressure {
+    /// `some` or `full`.
+    pub kind: String,
+    /// Share of the last 10 seconds, in percent.
+    pub avg10: f64,
+    /// Stall time since boot, in microseconds.
+    pub total: u64,
+}
+
+/// Parses `some avg10=1.23 avg60=0.50 avg300=0.10 total=123456` lines, as in
+/// `/proc/pressure/*` and the `*.pressure` files of cgroup v2.
+pub fn parse_pressure(content: &str) -> Vec<Pressure> {
+    content.lines()
+        .filter_map(|line| {
+            let kind = line.split_whitespace().next()?;
+            let value = |key: &str| line.split_whitespace().find_map(|field| field.strip_prefix(key)?.strip_prefix('='));
+            Some(Pressure {
+                kind: kind.to_string(),
+                avg10: value("avg10")?.parse().ok()?,
+                total: value("total")?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Pressure Stall Information: how much of the time tasks waited for CPU,
+/// memory or I/O, which shows a struggling board better than usage does.
+pub struct PressureCollector {
+    /// Stall totals at the previous refresh, per resource and kind.
+    last: Vec<(&'static str, String, u64)>,
+}
+
+impl PressureCollector {
+    /// `None` on kernels built without `CONFIG_PSI` or booted with `psi=0`.
+    pub fn discover(fs: &dyn SysFs) -> Option<PressureCollector> {
+        fs.read_to_string("/proc/pressure/cpu")?;
+        Some(PressureCollector { last: Vec::new() })
+    }
+}
+
+impl Collector for PressureCollector {
+    fn name(&self) -> &str {
+        "Pressure"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let mut totals = Vec::new();
+        for resource in RESOURCES {
+            let Some(content) = fs.read_to_string(&format!("/proc/pressure/{}", resource)) else {
+                continue;
+            };
+            for pressure in parse_pressure(&content) {
+                let series = |metric, unit| Series::new(metric, unit)
+                    .label("resource", resource)
+                    .label("kind", &pressure.kind)
+                    .name(format!("{} {}", resource, pressure.kind));
+                samples.push(Sample::new(series("psi_avg10", Unit::Percent), pressure.avg10));
+                samples.push(Sample::new(series("psi_stall", Unit::Second).kind(Kind::Counter), pressure.total as f64 / 1_000_000.0));
+
+                let before = self.last.iter()
+                    .find(|(other, kind, _)| *other == resource && *kind == pressure.kind)
+                    .map(|(_, _, total)| *total);
+                if let Some(before) = before {
+                    let stall = pressure.total.saturating_sub(before) as f64 / 1_000_000.0;
+                    samples.push(Sample::new(series("psi_stall_delta", Unit::Second).kind(Kind::Delta), stall));
+                }
+                totals.push((resource, pressure.kind, pressure.total));
+            }
+        }
+        self.last = totals;
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let chart = |resource: &str, title: &str| ChartSpec::new(title)
+            .series(SeriesFilter::metric("psi_avg10").label("resource", resource))
+            .y_bounds(0.0, 100.0);
+        vec![
+            vec![
+                chart("cpu", "CPU Pressure, avg10 (%)"),
+                chart("memory", "Memory Pressure, avg10 (%)"),
+                chart("io", "I/O Pressure, avg10 (%)"),
+            ],
+            vec![
+                ChartSpec::new("Stall Time per Interval, some (ms)")
+                    .series(SeriesFilter::metric("psi_stall_delta").label("kind", "some")),
+                ChartSpec::new("Stall Time per Interval, full (ms)")
+                    .series(SeriesFilter::metric("psi_stall_delta").label("kind", "full")),
+            ],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("psi_avg10").label("kind", "some")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::sysfs::{fixture, rewound, value};
+
+    #[test]
+    fn parses_some_and_full() {
+        let pressure = parse_pressure(&fixture("rk3588").read_to_string("/proc/pressure/io").unwrap());
+        assert_eq!(pressure, [
+            Pressure { kind: "some".to_string(), avg10: 21.06, total: 120331009 },
+            Pressure { kind: "full".to_string(), avg10: 9.88, total: 70211400 },
+        ]);
+        assert!(PressureCollector::discover(&fixture("rk3528")).is_none());
+    }
+
+    #[test]
+    fn charts_stall_time_deltas() {
+        let fs = fixture("rk3588");
+        let mut collector = PressureCollector::discover(&fs).unwrap();
+        // Stall times are read as they are, so the interval doesn't matter
+        let read = |collector: &mut PressureCollector, _| {
+            let mut samples = Vec::new();
+            collector.collect(&fs, &mut samples);
+            samples
+        };
+        let (first, samples) = rewound(&mut collector, Duration::ZERO, read, |collector| {
+            // Memory had stalled 250 ms less
+            collector.last.iter_mut().find(|(resource, kind, _)| *resource == "memory" && kind == "full").unwrap().2 -= 250_000;
+        });
+        assert_eq!(first.len(), 12);
+        assert_eq!(Unit::Second.format(value(&samples, r#"psi_stall_delta{resource="memory",kind="full"}"#)), "250.0 ms");
+        assert_eq!(value(&samples, r#"psi_stall_delta{resource="io",kind="some"}"#), 0.0);
+        assert_eq!(value(&samples, r#"psi_stall{resource="io",kind="some"}"#), 120.331009);
+        assert_eq!(value(&samples, r#"psi_avg10{resource="cpu",kind="some"}"#), 12.4);
+    }
+}
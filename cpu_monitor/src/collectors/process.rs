@@ -0,0 +1,650 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use crossterm::event::KeyCode;
+
+use crate::collector::{ChartSpec, Collector, Panel, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::cpu::read_total_jiffies;
+
+/// Processes charted every interval when none is selected, busiest first.
+const TOP_PROCESSES: usize = 5;
+
+/// How often the table is re-read. Scanning every process costs more than
+/// the rest of a short interval, so between refreshes the series repeat.
+pub(super) const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Rows on either side of the cursor whose open files are read. The list
+/// scrolls to keep the cursor in view, and no panel is taller than this.
+const SCREEN_ROWS: usize = 100;
+
+/// Accelerator device nodes and the name shown for them in the DEV column.
+/// `/dev/mali0` is the vendor Mali driver, which has no DRM node.
+const ACCELERATORS: [(&str, &str); 4] = [
+    ("/dev/mali0", "GPU"),
+    ("/dev/rknpu", "NPU"),
+    ("/dev/rga", "RGA"),
+    ("/dev/mpp_service", "MPP"),
+];
+
+/// One `/proc/<pid>` entry.
+#[derive(Debug, Clone, PartialEq)]
+struct Process {
+    pid: u32,
+    ppid: u32,
+    /// Short name from `stat`, e.g. `python3`.
+    name: String,
+    /// Full command line, or `[name]` for kernel threads.
+    command: String,
+    uid: u32,
+    state: char,
+    threads: u32,
+    /// Resident set size in bytes.
+    rss: u64,
+    /// utime + stime in jiffies since the process started.
+    ticks: u64,
+    /// Share of one core over the last interval, in percent.
+    cpu: f64,
+    /// Whether `devices` and `engines` were read at the last refresh, which
+    /// only happens for rows that can be seen or are charted.
+    scanned: bool,
+    /// Accelerators the process has open.
+    devices: Vec<&'static str>,
+    /// Busy time in ns per DRM engine, summed over the process's DRM clients.
+    engines: Vec<(String, u64)>,
+    /// Busiest GPU engine over the last interval in percent, or `None` without
+    /// a DRM client that reports engine time.
+    gpu: Option<f64>,
+}
+
+/// Reads a process from `stat`, `status` and `cmdline`. `None` if it exited
+/// while being read.
+fn read_process(fs: &dyn SysFs, pid: u32) -> Option<Process> {
+    let stat = fs.read_to_string(&format!("/proc/{}/stat", pid))?;
+    // The name is in parentheses and may itself contain spaces and parentheses
+    let name = &stat[stat.find('(')? + 1..stat.rfind(')')?];
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+
+    let status = fs.read_to_string(&format!("/proc/{}/status", pid)).unwrap_or_default();
+    let status_field = |key: &str| status.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    let cmdline = fs.read_to_string(&format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let command = cmdline.split('\0').filter(|arg| !arg.is_empty()).collect::<Vec<_>>().join(" ");
+
+    Some(Process {
+        pid,
+        ppid: field(1) as u32,
+        name: name.to_string(),
+        command: if command.is_empty() { format!("[{}]", name) } else { command },
+        uid: status_field("Uid") as u32,
+        state: fields.first().and_then(|state| state.chars().next()).unwrap_or('?'),
+        threads: field(17) as u32,
+        rss: status_field("VmRSS") * 1024,
+        ticks: field(11) + field(12),
+        cpu: 0.0,
+        scanned: false,
+        devices: Vec::new(),
+        engines: Vec::new(),
+        gpu: None,
+    })
+}
+
+/// Value of a `key:\tvalue` line in an fdinfo file.
+fn fdinfo_value<'a>(fdinfo: &'a str, key: &str) -> Option<&'a str> {
+    fdinfo.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix(':')).map(str::trim)
+}
+
+/// Accelerators open in the process, from the targets of `/proc/<pid>/fd`,
+/// and the busy time of its GPU clients. DRM drivers such as panfrost and
+/// panthor report `drm-engine-<engine>: <time> ns` in the fdinfo of each
+/// client; a client reached through several descriptors is counted once.
+fn read_accelerators(fs: &dyn SysFs, pid: u32) -> (Vec<&'static str>, Vec<(String, u64)>) {
+    let mut devices = Vec::new();
+    let mut clients = Vec::new();
+    let mut engines: Vec<(String, u64)> = Vec::new();
+    for fd in fs.read_dir(&format!("/proc/{}/fd", pid)) {
+        let Some(target) = fs.read_link(&format!("/proc/{}/fd/{}", pid, fd)) else {
+            continue;
+        };
+        let device = if let Some((_, name)) = ACCELERATORS.iter().find(|(path, _)| target == *path) {
+            *name
+        } else if target.starts_with("/dev/dri/") {
+            let fdinfo = fs.read_to_string(&format!("/proc/{}/fdinfo/{}", pid, fd)).unwrap_or_default();
+            // Newer rknpu drivers register the NPU as a DRM device too
+            if fdinfo_value(&fdinfo, "drm-driver") == Some("rknpu") {
+                "NPU"
+            } else {
+                let client = fdinfo_value(&fdinfo, "drm-client-id").unwrap_or(&fd).to_string();
+                if !clients.contains(&client) {
+                    clients.push(client);
+                    for (engine, time) in drm_engine_times(&fdinfo) {
+                        match engines.iter_mut().find(|(other, _)| other == engine) {
+                            Some((_, total)) => *total += time,
+                            None => engines.push((engine.to_string(), time)),
+                        }
+                    }
+                }
+                "GPU"
+            }
+        } else {
+            continue;
+        };
+        if !devices.contains(&device) {
+            devices.push(device);
+        }
+    }
+    (devices, engines)
+}
+
+/// `drm-engine-<engine>` busy times in ns. `drm-engine-capacity-<engine>`
+/// lines count engine instances and have no unit, so they are skipped.
+fn drm_engine_times(fdinfo: &str) -> Vec<(&str, u64)> {
+    fdinfo.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let engine = key.strip_prefix("drm-engine-")?;
+            let time = value.trim().strip_suffix(" ns")?.parse().ok()?;
+            Some((engine, time))
+        })
+        .collect()
+}
+
+/// User names by uid from `/etc/passwd`.
+fn read_users(fs: &dyn SysFs) -> HashMap<u32, String> {
+    fs.read_to_string("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    Pid,
+    User,
+    Cpu,
+    Gpu,
+    Rss,
+    Threads,
+    State,
+    Devices,
+    Command,
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 9] = [
+        SortColumn::Pid, SortColumn::User, SortColumn::Cpu, SortColumn::Gpu, SortColumn::Rss,
+        SortColumn::Threads, SortColumn::State, SortColumn::Devices, SortColumn::Command,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            SortColumn::Pid => "PID",
+            SortColumn::User => "USER",
+            SortColumn::Cpu => "CPU%",
+            SortColumn::Gpu => "GPU%",
+            SortColumn::Rss => "RSS",
+            SortColumn::Threads => "THR",
+            SortColumn::State => "S",
+            SortColumn::Devices => "DEV",
+            SortColumn::Command => "COMMAND",
+        }
+    }
+
+    /// The next column to the right, or to the left with `step` -1.
+    fn cycle(&self, step: isize) -> SortColumn {
+        let index = SortColumn::ALL.iter().position(|column| column == self).unwrap_or(0) as isize;
+        SortColumn::ALL[(index + step).rem_euclid(SortColumn::ALL.len() as isize) as usize]
+    }
+}
+
+/// A top-like process table. The busiest processes, or the one picked with
+/// Enter, get CPU and memory series so they can be charted and recorded.
+pub struct ProcessCollector {
+    users: HashMap<u32, String>,
+    processes: Vec<Process>,
+    /// Total jiffies at the previous refresh.
+    last_jiffies: Option<u64>,
+    last_refresh: Option<Instant>,
+    sort: SortColumn,
+    descending: bool,
+    tree: bool,
+    query: String,
+    searching: bool,
+    /// Process under the cursor, followed by pid as rows move around.
+    cursor: Option<u32>,
+    /// Process whose history is charted instead of the busiest ones.
+    focused: Option<u32>,
+}
+
+impl ProcessCollector {
+    pub fn new(fs: &dyn SysFs) -> ProcessCollector {
+        ProcessCollector {
+            users: read_users(fs),
+            processes: Vec::new(),
+            last_jiffies: None,
+            last_refresh: None,
+            sort: SortColumn::Cpu,
+            descending: true,
+            tree: false,
+            query: String::new(),
+            searching: false,
+            cursor: None,
+            focused: None,
+        }
+    }
+
+    /// Whether typed keys go into the filter.
+    pub(super) fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    fn user(&self, uid: u32) -> String {
+        self.users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+    }
+
+    /// Replaces the table with a fresh read of /proc. CPU usage is the change
+    /// in a process's jiffies over the change in all cores' jiffies, scaled so
+    /// one busy core is 100%, as in top. GPU usage is the change in engine
+    /// busy time over the wall time between refreshes.
+    fn refresh(&mut self, fs: &dyn SysFs, now: Instant) {
+        let pids: Vec<u32> = fs.read_dir("/proc").iter().filter_map(|entry| entry.parse().ok()).collect();
+        self.refresh_pids(fs, now, &pids);
+    }
+
+    /// Like `refresh`, but only reads the given processes, e.g. a cgroup's.
+    /// Open files and fdinfo are read only for the processes `scanned` picks.
+    pub(super) fn refresh_pids(&mut self, fs: &dyn SysFs, now: Instant, pids: &[u32]) {
+        let jiffies = read_total_jiffies(fs);
+        let elapsed = match (jiffies, self.last_jiffies) {
+            (Some((now, _)), Some(last)) => now.saturating_sub(last),
+            _ => 0,
+        };
+        let cores = jiffies.map_or(1, |(_, cores)| cores);
+        self.last_jiffies = jiffies.map(|(now, _)| now);
+        let wall = self.last_refresh.map_or(0, |last| now.duration_since(last).as_nanos() as u64);
+        self.last_refresh = Some(now);
+
+        let previous: HashMap<u32, Process> = std::mem::take(&mut self.processes)
+            .into_iter()
+            .map(|process| (process.pid, process))
+            .collect();
+        self.processes = pids.iter().filter_map(|pid| read_process(fs, *pid)).collect();
+        for process in &mut self.processes {
+            let last = previous.get(&process.pid);
+            if elapsed > 0
+                && let Some(last) = last
+            {
+                process.cpu = process.ticks.saturating_sub(last.ticks) as f64 / elapsed as f64 * cores as f64 * 100.0;
+            }
+        }
+
+        let scanned = self.scanned();
+        for process in self.processes.iter_mut().filter(|process| scanned.contains(&process.pid)) {
+            (process.devices, process.engines) = read_accelerators(fs, process.pid);
+            process.scanned = true;
+            if process.engines.is_empty() {
+                continue;
+            }
+            // Engines that weren't there last time belong to a client opened
+            // since; a process that just scrolled into view has no baseline yet
+            let last = previous.get(&process.pid);
+            process.gpu = Some(match last {
+                Some(last) if wall > 0 && last.scanned => process.engines.iter()
+                    .map(|(engine, time)| {
+                        let before = last.engines.iter().find(|(other, _)| other == engine).map_or(0, |(_, time)| *time);
+                        time.saturating_sub(before) as f64 / wall as f64 * 100.0
+                    })
+                    .fold(0.0, f64::max),
+                _ => 0.0,
+            });
+        }
+    }
+
+    /// Processes whose open files are read: every one while sorting by a
+    /// column that needs them, otherwise the charted ones and the rows that
+    /// can be on screen.
+    fn scanned(&self) -> HashSet<u32> {
+        if matches!(self.sort, SortColumn::Gpu | SortColumn::Devices) {
+            return self.processes.iter().map(|process| process.pid).collect();
+        }
+        let visible = self.visible();
+        let cursor = self.cursor_row(&visible);
+        visible[cursor.saturating_sub(SCREEN_ROWS)..(cursor + SCREEN_ROWS).min(visible.len())].iter()
+            .map(|(index, _)| self.processes[*index].pid)
+            .chain(self.charted().iter().map(|process| process.pid))
+            .collect()
+    }
+
+    fn compare(&self, a: &Process, b: &Process) -> Ordering {
+        let ordering = match self.sort {
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::User => self.user(a.uid).cmp(&self.user(b.uid)),
+            SortColumn::Cpu => a.cpu.total_cmp(&b.cpu),
+            SortColumn::Gpu => a.gpu.unwrap_or(-1.0).total_cmp(&b.gpu.unwrap_or(-1.0)),
+            SortColumn::Rss => a.rss.cmp(&b.rss),
+            SortColumn::Threads => a.threads.cmp(&b.threads),
+            SortColumn::State => a.state.cmp(&b.state),
+            SortColumn::Devices => a.devices.cmp(&b.devices),
+            SortColumn::Command => a.command.cmp(&b.command),
+        };
+        // Ties keep a stable order by pid
+        let ordering = ordering.then(a.pid.cmp(&b.pid));
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+
+    fn matches(&self, process: &Process) -> bool {
+        let query = self.query.to_lowercase();
+        process.command.to_lowercase().contains(&query) || process.name.to_lowercase().contains(&query)
+    }
+
+    /// Indices into `processes` with their depth in the tree. The tree view
+    /// sorts siblings and keeps the ancestors of every match.
+    fn visible(&self) -> Vec<(usize, usize)> {
+        let mut order: Vec<usize> = (0..self.processes.len()).collect();
+        order.sort_by(|a, b| self.compare(&self.processes[*a], &self.processes[*b]));
+        if !self.tree {
+            return order.into_iter().filter(|index| self.matches(&self.processes[*index])).map(|index| (index, 0)).collect();
+        }
+
+        let pids: HashSet<u32> = self.processes.iter().map(|process| process.pid).collect();
+        let mut children: HashMap<u32, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for index in order {
+            let process = &self.processes[index];
+            if pids.contains(&process.ppid) {
+                children.entry(process.ppid).or_default().push(index);
+            } else {
+                roots.push(index);
+            }
+        }
+
+        let mut rows = Vec::new();
+        for root in roots {
+            self.add_subtree(root, 0, &children, &mut rows);
+        }
+        rows
+    }
+
+    /// Appends the process and its descendants, if any of them matches.
+    fn add_subtree(&self, index: usize, depth: usize, children: &HashMap<u32, Vec<usize>>, rows: &mut Vec<(usize, usize)>) -> bool {
+        let position = rows.len();
+        let mut matched = false;
+        rows.push((index, depth));
+        for child in children.get(&self.processes[index].pid).into_iter().flatten() {
+            matched |= self.add_subtree(*child, depth + 1, children, rows);
+        }
+        matched |= self.matches(&self.processes[index]);
+        if !matched {
+            rows.truncate(position);
+        }
+        matched
+    }
+
+    /// Row of the cursor among the visible rows, or the first row.
+    fn cursor_row(&self, visible: &[(usize, usize)]) -> usize {
+        self.cursor
+            .and_then(|pid| visible.iter().position(|(index, _)| self.processes[*index].pid == pid))
+            .unwrap_or(0)
+    }
+
+    fn move_cursor(&mut self, step: isize) {
+        let visible = self.visible();
+        if visible.is_empty() {
+            return;
+        }
+        let current = self.cursor_row(&visible) as isize;
+        let next = (current + step).clamp(0, visible.len() as isize - 1) as usize;
+        self.cursor = Some(self.processes[visible[next].0].pid);
+    }
+
+    /// Processes that get series: the focused one, or the busiest few.
+    fn charted(&self) -> Vec<&Process> {
+        match self.focused {
+            Some(pid) => self.processes.iter().filter(|process| process.pid == pid).collect(),
+            None => {
+                // Memory breaks ties so an idle system still charts its largest processes
+                let mut busiest: Vec<&Process> = self.processes.iter().collect();
+                busiest.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(b.rss.cmp(&a.rss)));
+                busiest.truncate(TOP_PROCESSES);
+                busiest
+            }
+        }
+    }
+}
+
+/// Formats a size in MiB, like the Memory tab.
+fn format_rss(bytes: u64) -> String {
+    Unit::Byte.format(bytes as f64)
+}
+
+impl Collector for ProcessCollector {
+    fn name(&self) -> &str {
+        "Processes"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let now = Instant::now();
+        if self.last_refresh.is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL) {
+            self.refresh(fs, now);
+        }
+
+        let running = self.processes.iter().filter(|process| process.state == 'R').count();
+        samples.push(Sample::new(Series::new("processes", Unit::Count).name("Processes"), self.processes.len() as f64));
+        samples.push(Sample::new(Series::new("processes_running", Unit::Count).name("Running"), running as f64));
+
+        for process in self.charted() {
+            let series = |metric, unit| Series::new(metric, unit)
+                .label("pid", process.pid)
+                .name(format!("{} ({})", process.name, process.pid))
+                .top_n();
+            samples.push(Sample::new(series("process_cpu", Unit::Percent), process.cpu));
+            samples.push(Sample::new(series("process_rss", Unit::Byte), process.rss as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let filter = |metric: &str| match self.focused {
+            Some(pid) => SeriesFilter::metric(metric).label("pid", pid.to_string()),
+            None => SeriesFilter::metric(metric),
+        };
+        let subject = match self.focused.and_then(|pid| self.processes.iter().find(|process| process.pid == pid)) {
+            Some(process) => format!("{} ({})", process.name, process.pid),
+            None => format!("Top {} Processes", TOP_PROCESSES),
+        };
+        vec![vec![
+            ChartSpec::new(format!("{} CPU (%)", subject)).series(filter("process_cpu")),
+            ChartSpec::new(format!("{} RSS (MiB)", subject)).series(filter("process_rss")),
+        ]]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("processes"), SeriesFilter::metric("processes_running")]
+    }
+
+    fn panel(&self) -> Option<Panel> {
+        let visible = self.visible();
+        let header = SortColumn::ALL.iter()
+            .map(|column| {
+                let arrow = if *column != self.sort { "" } else if self.descending { "▼" } else { "▲" };
+                let title = format!("{}{}", column.title(), arrow);
+                match column {
+                    SortColumn::Pid => format!("  {:>7}", title),
+                    SortColumn::User => format!("{:<10}", title),
+                    SortColumn::Cpu | SortColumn::Gpu => format!("{:>6}", title),
+                    SortColumn::Rss => format!("{:>12}", title),
+                    SortColumn::Threads => format!("{:>4}", title),
+                    SortColumn::State => format!("{:<2}", title),
+                    SortColumn::Devices => format!("{:<15}", title),
+                    SortColumn::Command => title,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let rows = visible.iter()
+            .map(|(index, depth)| {
+                let process = &self.processes[*index];
+                let focus = if self.focused == Some(process.pid) { '*' } else { ' ' };
+                let branch = if *depth > 0 { format!("{}└─ ", "   ".repeat(depth - 1)) } else { String::new() };
+                let gpu = process.gpu.map_or("-".to_string(), |gpu| format!("{:.1}", gpu));
+                format!(
+                    "{} {:>7} {:<10} {:>6.1} {:>6} {:>12} {:>4} {:<2} {:<15} {}{}",
+                    focus, process.pid, self.user(process.uid), process.cpu, gpu, format_rss(process.rss),
+                    process.threads, process.state, process.devices.join(","), branch, process.command,
+                )
+            })
+            .collect();
+        let selected = self.cursor_row(&visible);
+
+        let mut title = format!("Processes ({} of {})", visible.len(), self.processes.len());
+        if self.searching || !self.query.is_empty() {
+            title.push_str(&format!(" - filter: {}{}", self.query, if self.searching { "_" } else { "" }));
+        }
+        if self.tree {
+            title.push_str(" - tree");
+        }
+        title.push_str(" - ↑/↓ select, </> sort, i invert, t tree, / filter, Enter chart");
+        Some(Panel { title, header, rows, selected })
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        if self.searching {
+            match key {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                _ => return false,
+            }
+            return true;
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-20),
+            KeyCode::PageDown => self.move_cursor(20),
+            KeyCode::Char('<') => self.sort = self.sort.cycle(-1),
+            KeyCode::Char('>') => self.sort = self.sort.cycle(1),
+            KeyCode::Char('i') => self.descending = !self.descending,
+            KeyCode::Char('t') => self.tree = !self.tree,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Enter => {
+                // Enter on the charted process goes back to the busiest ones
+                let cursor = self.cursor.or_else(|| self.visible().first().map(|(index, _)| self.processes[*index].pid));
+                self.focused = if self.focused == cursor { None } else { cursor };
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+    use crate::sysfs::{fixture, rewound};
+
+    #[test]
+    fn reads_proc_entries() {
+        let fs = fixture("rk3588");
+        let python = read_process(&fs, 1534).unwrap();
+        assert_eq!(python.command, "python3 yolov8_infer.py --npu");
+        assert_eq!((python.ppid, python.uid, python.state, python.threads), (873, 1000, 'R', 6));
+        assert_eq!((python.rss, python.ticks), (412160 * 1024, 6010));
+        assert_eq!(read_process(&fs, 2).unwrap().command, "[kthreadd]");
+        assert_eq!(read_users(&fs).get(&1000).map(String::as_str), Some("rock"));
+    }
+
+    #[test]
+    fn attributes_accelerators() {
+        let fs = fixture("rk3588");
+        // Two descriptors on the same DRM client count its engine time once
+        assert_eq!(read_accelerators(&fs, 1534), (vec!["NPU", "GPU"], vec![("panthor".to_string(), 2_500_000_000)]));
+        assert_eq!(read_accelerators(&fs, 2001).0, ["MPP", "RGA", "GPU"]);
+        assert_eq!(read_accelerators(&fs, 1290).0, ["NPU"]);
+        assert_eq!(read_accelerators(&fs, 873), (vec![], vec![]));
+    }
+
+    #[test]
+    fn computes_usage_over_the_interval() {
+        let fs = fixture("rk3588");
+        let mut collector = ProcessCollector::new(&fs);
+        rewound(&mut collector, Duration::from_secs(1), |collector, now| collector.refresh(&fs, now), |collector| {
+            // python3 used 50 jiffies while all 8 cores counted 400, and kept
+            // the GPU busy for half a second
+            let python = collector.processes.iter_mut().find(|process| process.pid == 1534).unwrap();
+            python.ticks -= 50;
+            python.engines[0].1 -= 500_000_000;
+            collector.last_jiffies = collector.last_jiffies.map(|jiffies| jiffies - 400);
+        });
+
+        let gpu = |pid| collector.processes.iter().find(|process| process.pid == pid).unwrap().gpu;
+        assert_eq!((gpu(1534), gpu(2001), gpu(873)), (Some(50.0), Some(0.0), None));
+        let python = collector.processes.iter().find(|process| process.pid == 1534).unwrap();
+        assert_eq!(python.cpu, 100.0);
+        let charted: Vec<u32> = collector.charted().iter().map(|process| process.pid).collect();
+        assert_eq!(charted, [1534, 2001, 1290, 1, 412]);
+    }
+
+    #[test]
+    fn refreshes_on_its_own_cadence() {
+        let fs = fixture("rk3588");
+        let mut collector = ProcessCollector::new(&fs);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        let first = collector.last_refresh;
+        collector.collect(&fs, &mut samples);
+        assert_eq!(collector.last_refresh, first);
+        // The previous table is repeated rather than re-read
+        assert_eq!(samples.len(), 2 * (2 + 2 * TOP_PROCESSES));
+        assert!(collector.processes.iter().all(|process| process.scanned));
+    }
+
+    #[test]
+    fn sorts_filters_and_builds_the_tree() {
+        let fs = fixture("rk3588");
+        let mut collector = ProcessCollector::new(&fs);
+        collector.refresh(&fs, Instant::now());
+        let pids = |collector: &ProcessCollector| collector.visible().iter()
+            .map(|(index, depth)| (collector.processes[*index].pid, *depth))
+            .collect::<Vec<_>>();
+
+        collector.handle_key(KeyCode::Char('>'));
+        collector.handle_key(KeyCode::Char('>'));
+        assert_eq!(collector.sort, SortColumn::Rss);
+        assert_eq!(pids(&collector)[0], (1534, 0));
+
+        collector.handle_key(KeyCode::Char('t'));
+        collector.handle_key(KeyCode::Char('i'));
+        assert_eq!(pids(&collector), [(2, 0), (1, 0), (412, 1), (873, 2), (2001, 3), (1534, 3), (1290, 1)]);
+
+        for key in [KeyCode::Char('/'), KeyCode::Char('g'), KeyCode::Char('s'), KeyCode::Char('t'), KeyCode::Enter] {
+            collector.handle_key(key);
+        }
+        assert_eq!(pids(&collector), [(1, 0), (412, 1), (873, 2), (2001, 3)]);
+
+        collector.handle_key(KeyCode::Down);
+        collector.handle_key(KeyCode::Enter);
+        assert_eq!(collector.focused, Some(412));
+        assert_eq!(collector.layout()[0][0].title, "sshd (412) CPU (%)");
+    }
+}
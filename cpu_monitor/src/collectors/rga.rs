@@ -0,0 +1,111 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::clk::ClockTree;
+
+const RKRGA_LOAD: &str = "/sys/kernel/debug/rkrga/load";
+
+/// Parses the rkrga debugfs load into one value per scheduler, i.e. per RGA
+/// core. Each `scheduler[0]: rga3_core0` line is followed by its load, printed
+/// as "load: 7%" by older drivers and "load = 7%" by newer ones.
+fn parse_rga_load(content: &str) -> Vec<(String, f64)> {
+    let mut loads = Vec::new();
+    let mut scheduler = None;
+    for line in content.lines() {
+        if let Some((_, name)) = line.split_once("]:") {
+            scheduler = Some(name.trim().to_string());
+        } else if let Some(load_str) = line.split_once("load:").or_else(|| line.split_once("load =")).map(|(_, load)| load)
+            && let Some(percent_str) = load_str.split('%').next()
+            && let Ok(load) = percent_str.trim().parse::<f64>()
+        {
+            loads.push((scheduler.take().unwrap_or_else(|| "rga".to_string()), load));
+        }
+    }
+    loads
+}
+
+/// RGA load per scheduler from debugfs and the RGA clocks from clk_summary.
+pub struct RgaCollector {
+    /// RGA clocks found in clk_summary at startup, e.g. `aclk_rga2e` on
+    /// RK3528 or `aclk_rga3_0` and `clk_rga2_core` on RK3588.
+    clocks: Vec<String>,
+    tree: ClockTree,
+}
+
+impl RgaCollector {
+    /// `None` when there is neither an rkrga load file nor an RGA clock.
+    pub fn discover(fs: &dyn SysFs, tree: &ClockTree) -> Option<RgaCollector> {
+        // Root muxes such as aclk_rga3_root feed several cores, so only leaf clocks are charted
+        let clocks: Vec<String> = tree.clocks().iter()
+            .map(|clock| clock.name.clone())
+            .filter(|name| name.contains("rga") && !name.ends_with("_root"))
+            .collect();
+        if clocks.is_empty() && fs.read_to_string(RKRGA_LOAD).is_none() {
+            return None;
+        }
+        Some(RgaCollector { clocks, tree: tree.clone() })
+    }
+}
+
+impl Collector for RgaCollector {
+    fn name(&self) -> &str {
+        "RGA"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        let loads = fs.read_to_string(RKRGA_LOAD).map(|content| parse_rga_load(&content)).unwrap_or_default();
+        for (scheduler, load) in loads {
+            samples.push(Sample::new(Series::new("rga_usage", Unit::Percent).label("scheduler", &scheduler).name(scheduler), load));
+        }
+
+        for clock in &self.clocks {
+            let rate = self.tree.rate(clock);
+            samples.push(Sample::new(Series::new("rga_frequency", Unit::Hertz).label("clock", clock).name(clock), rate as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        vec![
+            vec![ChartSpec::new("RGA Usage (%)").series(SeriesFilter::metric("rga_usage")).y_bounds(0.0, 100.0)],
+            vec![ChartSpec::new("RGA Clocks (MHz)").series(SeriesFilter::metric("rga_frequency"))],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("rga_usage")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::{fixture, value};
+
+    #[test]
+    fn reads_both_load_formats() {
+        let load = |board| parse_rga_load(&fixture(board).read_to_string(RKRGA_LOAD).unwrap());
+        assert_eq!(load("rk3528"), [("rga2".to_string(), 7.0)]);
+        assert_eq!(load("rk3588"), [
+            ("rga3_core0".to_string(), 5.0),
+            ("rga3_core1".to_string(), 0.0),
+            ("rga2".to_string(), 2.0),
+        ]);
+    }
+
+    #[test]
+    fn discovers_rga_clocks() {
+        let fs = fixture("rk3588");
+        let mut collector = RgaCollector::discover(&fs, &ClockTree::new(&fs)).unwrap();
+        assert_eq!(collector.clocks, ["aclk_rga3_0", "aclk_rga3_1", "aclk_rga2", "hclk_rga2", "clk_rga2_core"]);
+
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        assert_eq!(value(&samples, r#"rga_usage{scheduler="rga2"}"#), 2.0);
+        assert_eq!(value(&samples, r#"rga_frequency{clock="clk_rga2_core"}"#), 800_000_000.0);
+
+        let fs = fixture("rk3528");
+        assert_eq!(RgaCollector::discover(&fs, &ClockTree::new(&fs)).unwrap().clocks, ["aclk_rga2e", "clk_core_rga2e", "hclk_rga2e"]);
+        let fs = fixture("empty");
+        assert!(RgaCollector::discover(&fs, &ClockTree::new(&fs)).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+
+const THERMAL: &str = "/sys/class/thermal";
+
+/// A `thermal_zoneN` node and its `type`, e.g. `bigcore0-thermal`.
+#[derive(Debug, Clone)]
+struct ThermalZone {
+    id: String,
+    kind: String,
+}
+
+impl ThermalZone {
+    fn path(&self, attribute: &str) -> String {
+        format!("{}/{}/{}", THERMAL, self.id, attribute)
+    }
+
+    /// Short name for legends, `bigcore0` rather than `bigcore0-thermal`.
+    fn name(&self) -> &str {
+        self.kind.strip_suffix("-thermal").unwrap_or(&self.kind)
+    }
+}
+
+/// A trip point of a zone: the temperature at which the kernel starts
+/// passive throttling, switches on active cooling, or shuts down.
+#[derive(Debug, Clone, PartialEq)]
+struct TripPoint {
+    index: usize,
+    kind: String,
+    temperature: f64,
+}
+
+fn discover_thermal_zones(fs: &dyn SysFs) -> Vec<ThermalZone> {
+    let mut zones: Vec<(usize, ThermalZone)> = fs.read_dir(THERMAL)
+        .into_iter()
+        .filter_map(|id| {
+            let number = id.strip_prefix("thermal_zone")?.parse().ok()?;
+            let kind = fs.read_to_string(&format!("{}/{}/type", THERMAL, id))
+                .map(|kind| kind.trim().to_string())
+                .unwrap_or_else(|| id.clone());
+            Some((number, ThermalZone { id, kind }))
+        })
+        .collect();
+    // Directory order puts thermal_zone10 before thermal_zone2
+    zones.sort_by_key(|(number, _)| *number);
+    zones.into_iter().map(|(_, zone)| zone).collect()
+}
+
+/// Reads a millidegree attribute in degrees.
+fn read_millidegrees(fs: &dyn SysFs, path: &str) -> Option<f64> {
+    let millidegrees = fs.read_to_string(path)?.trim().parse::<f64>().ok()?;
+    Some(millidegrees / 1000.0) // Convert millidegrees to degrees
+}
+
+fn read_trip_points(fs: &dyn SysFs, zone: &ThermalZone) -> Vec<TripPoint> {
+    let mut trips: Vec<TripPoint> = fs.read_dir(&format!("{}/{}", THERMAL, zone.id))
+        .iter()
+        .filter_map(|name| name.strip_prefix("trip_point_")?.strip_suffix("_temp")?.parse::<usize>().ok())
+        .filter_map(|index| {
+            let temperature = read_millidegrees(fs, &zone.path(&format!("trip_point_{}_temp", index)))?;
+            let kind = fs.read_to_string(&zone.path(&format!("trip_point_{}_type", index)))
+                .map(|kind| kind.trim().to_string())
+                .unwrap_or_default();
+            Some(TripPoint { index, kind, temperature })
+        })
+        .collect();
+    trips.sort_by_key(|trip| trip.index);
+    trips
+}
+
+/// Temperature of every thermal zone with its trip points.
+pub struct ThermalCollector {
+    zones: Vec<ThermalZone>,
+}
+
+impl ThermalCollector {
+    pub fn new(fs: &dyn SysFs) -> ThermalCollector {
+        ThermalCollector { zones: discover_thermal_zones(fs) }
+    }
+}
+
+impl Collector for ThermalCollector {
+    fn name(&self) -> &str {
+        "Thermal"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        for zone in &self.zones {
+            // A powered-down zone, e.g. the GPU's, fails the read with EAGAIN; leave a gap rather than 0 °C
+            if let Some(temperature) = read_millidegrees(fs, &zone.path("temp")) {
+                samples.push(Sample::new(
+                    Series::new("temperature", Unit::Celsius).label("zone", &zone.id).label("type", &zone.kind).name(zone.name()),
+                    temperature,
+                ));
+            }
+
+            // Trip points are writable on some kernels, so they are read every interval
+            for trip in read_trip_points(fs, zone) {
+                samples.push(Sample::new(
+                    Series::new("thermal_trip", Unit::Celsius)
+                        .label("zone", &zone.id)
+                        .label("trip", trip.index)
+                        .label("type", &trip.kind)
+                        .name(format!("{} {:.0}°C", trip.kind, trip.temperature)),
+                    trip.temperature,
+                ));
+            }
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        // One chart per zone with its trip points as threshold lines, two per row
+        let zones: Vec<ChartSpec> = self.zones.iter()
+            .map(|zone| ChartSpec::new(format!("{} Temperature (°C)", zone.name()))
+                .series(SeriesFilter::metric("temperature").label("zone", &zone.id))
+                .threshold(SeriesFilter::metric("thermal_trip").label("zone", &zone.id)))
+            .collect();
+        zones.chunks(2).map(|row| row.to_vec()).collect()
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("temperature")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+
+    #[test]
+    fn enumerates_zones_by_type() {
+        let zones = discover_thermal_zones(&fixture("rk3588"));
+        let names: Vec<_> = zones.iter().map(|zone| zone.name()).collect();
+        assert_eq!(names, ["soc", "bigcore0", "bigcore1", "littlecore", "center", "gpu", "npu"]);
+        assert_eq!(read_millidegrees(&fixture("rk3588"), &zones[0].path("temp")), Some(41.666));
+    }
+
+    #[test]
+    fn reads_trip_points() {
+        let fs = fixture("rk3566");
+        let zones = discover_thermal_zones(&fs);
+        let trips = read_trip_points(&fs, &zones[0]);
+        let trips: Vec<_> = trips.iter().map(|trip| (trip.kind.as_str(), trip.temperature)).collect();
+        assert_eq!(trips, [("passive", 75.0), ("passive", 85.0), ("critical", 115.0)]);
+        assert_eq!(read_trip_points(&fs, &zones[1]).len(), 1);
+    }
+
+    #[test]
+    fn emits_temperatures_and_trips() {
+        let fs = fixture("rk3528");
+        let mut collector = ThermalCollector::new(&fs);
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+
+        let keys: Vec<_> = samples.iter().map(|sample| sample.series.key()).collect();
+        assert_eq!(keys[0], r#"temperature{zone="thermal_zone0",type="soc-thermal"}"#);
+        assert_eq!(keys[3], r#"thermal_trip{zone="thermal_zone0",trip="2",type="critical"}"#);
+        assert_eq!(samples[3].series.name, "critical 115°C");
+        assert_eq!(collector.layout().len(), 1);
+
+        // A zone that can't be read has no temperature sample at all
+        collector.zones.push(ThermalZone { id: "thermal_zone9".to_string(), kind: "gpu-thermal".to_string() });
+        samples.clear();
+        collector.collect(&fs, &mut samples);
+        assert!(!samples.iter().any(|sample| sample.series.label_value("zone") == Some("thermal_zone9")));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::collector::{ChartSpec, Collector, SeriesFilter};
+use crate::metrics::{Sample, Series, Unit};
+use crate::sysfs::SysFs;
+use super::clk::ClockTree;
+use super::devfreq::{DevfreqDevice, DevfreqKind};
+
+/// mpp_service is in procfs on Rockchip BSP kernels and in debugfs on some
+/// older ones.
+const MPP_SERVICE: [&str; 2] = ["/proc/mpp_service", "/sys/kernel/debug/mpp_service"];
+
+/// Splits `rkvdec-core0 load:  42.50% utilization:  38.20%` lines into device
+/// and load. The file only has numbers once `load_interval` is non-zero.
+fn parse_mpp_load(content: &str) -> Vec<(String, f64)> {
+    content.lines()
+        .filter_map(|line| {
+            let (device, rest) = line.split_once(" load:")?;
+            let load = rest.split('%').next()?.trim().parse::<f64>().ok()?;
+            Some((device.trim().to_string(), load))
+        })
+        .collect()
+}
+
+/// Counts open sessions per block in `sessions-info`, whose table rows start
+/// with `|<session>|<device>|`. Each device driver prints its own columns
+/// after those two.
+fn count_sessions(content: &str) -> Vec<(DevfreqKind, usize)> {
+    let mut counts: Vec<(DevfreqKind, usize)> = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split('|').map(str::trim).filter(|field| !field.is_empty());
+        let (Some(session), Some(device)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if session.parse::<u32>().is_err() {
+            continue;
+        }
+        let kind = DevfreqKind::classify(device);
+        match counts.iter_mut().find(|(other, _)| *other == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts
+}
+
+/// Hardware video decoders and encoders: devfreq load and clock, mpp_service
+/// load and sessions, and the block clocks from clk_summary.
+pub struct VideoCollector {
+    devfreq: Vec<DevfreqDevice>,
+    /// mpp_service directory, if the kernel has one.
+    mpp: Option<&'static str>,
+    /// Codec clocks found in clk_summary at startup.
+    clocks: Vec<String>,
+    tree: ClockTree,
+    /// Blocks found by any of the above, in `DevfreqKind` order.
+    kinds: Vec<DevfreqKind>,
+}
+
+impl VideoCollector {
+    pub fn discover(fs: &dyn SysFs, devices: &[DevfreqDevice], tree: &ClockTree) -> Option<VideoCollector> {
+        let devfreq: Vec<DevfreqDevice> = devices.iter().filter(|device| device.kind.is_video()).cloned().collect();
+        let mpp = MPP_SERVICE.into_iter().find(|root| {
+            fs.read_to_string(&format!("{}/load", root)).is_some()
+                || fs.read_to_string(&format!("{}/sessions-info", root)).is_some()
+        });
+        // Parent muxes such as aclk_vdpu_root feed several blocks, so only leaf clocks are charted
+        let clocks: Vec<String> = tree.clocks().iter()
+            .map(|clock| clock.name.clone())
+            .filter(|name| DevfreqKind::classify(name).is_video() && !name.ends_with("_root"))
+            .collect();
+        if devfreq.is_empty() && mpp.is_none() && clocks.is_empty() {
+            return None;
+        }
+
+        let mut kinds: Vec<DevfreqKind> = devfreq.iter().map(|device| device.kind)
+            .chain(clocks.iter().map(|name| DevfreqKind::classify(name)))
+            .chain(mpp.iter()
+                .filter_map(|root| fs.read_to_string(&format!("{}/load", root)))
+                .flat_map(|content| parse_mpp_load(&content))
+                .map(|(device, _)| DevfreqKind::classify(&device))
+                .filter(|kind| kind.is_video()))
+            .collect();
+        kinds.sort_by_key(|kind| *kind as usize);
+        kinds.dedup();
+        Some(VideoCollector { devfreq, mpp, clocks, tree: tree.clone(), kinds })
+    }
+}
+
+impl Collector for VideoCollector {
+    fn name(&self) -> &str {
+        "Video"
+    }
+
+    fn collect(&mut self, fs: &dyn SysFs, samples: &mut Vec<Sample>) {
+        for device in &self.devfreq {
+            let (load, freq) = device.read(fs);
+            let series = |metric, unit| Series::new(metric, unit)
+                .label("device", &device.name)
+                .label("block", device.kind.label())
+                .name(&device.name);
+            samples.push(Sample::new(series("video_load", Unit::Percent), load));
+            samples.push(Sample::new(series("video_frequency", Unit::Hertz), freq as f64));
+        }
+
+        if let Some(root) = self.mpp {
+            if let Some(content) = fs.read_to_string(&format!("{}/load", root)) {
+                for (device, load) in parse_mpp_load(&content) {
+                    let block = DevfreqKind::classify(&device).label();
+                    samples.push(Sample::new(
+                        Series::new("video_load", Unit::Percent).label("device", &device).label("block", block).name(&device),
+                        load,
+                    ));
+                }
+            }
+
+            let sessions = fs.read_to_string(&format!("{}/sessions-info", root))
+                .map(|content| count_sessions(&content))
+                .unwrap_or_default();
+            for kind in &self.kinds {
+                let count = sessions.iter().find(|(other, _)| other == kind).map_or(0, |(_, count)| *count);
+                samples.push(Sample::new(
+                    Series::new("video_sessions", Unit::Count).label("block", kind.label()).name(kind.label()),
+                    count as f64,
+                ));
+            }
+        }
+
+        for clock in &self.clocks {
+            let rate = self.tree.rate(clock);
+            samples.push(Sample::new(Series::new("video_clock", Unit::Hertz).label("clock", clock).name(clock), rate as f64));
+        }
+    }
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        let mut load_row = vec![ChartSpec::new("Video Load (%)").series(SeriesFilter::metric("video_load")).y_bounds(0.0, 100.0)];
+        if self.mpp.is_some() {
+            load_row.push(ChartSpec::new("Video Sessions").series(SeriesFilter::metric("video_sessions")));
+        }
+        vec![
+            load_row,
+            vec![ChartSpec::new("Video Clocks (MHz)")
+                .series(SeriesFilter::metric("video_frequency"))
+                .series(SeriesFilter::metric("video_clock"))],
+        ]
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        vec![SeriesFilter::metric("video_load"), SeriesFilter::metric("video_sessions")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::devfreq::discover_devfreq_devices;
+    use crate::sysfs::{fixture, value};
+
+    #[test]
+    fn parses_mpp_service() {
+        let fs = fixture("rk3588");
+        let load = parse_mpp_load(&fs.read_to_string("/proc/mpp_service/load").unwrap());
+        assert_eq!(load[0], ("rkvdec-core0".to_string(), 42.5));
+        assert_eq!(load[3], ("jpegd".to_string(), 3.0));
+
+        let sessions = count_sessions(&fs.read_to_string("/proc/mpp_service/sessions-info").unwrap());
+        assert_eq!(sessions, [(DevfreqKind::Rkvenc, 1), (DevfreqKind::Rkvdec, 2), (DevfreqKind::Jpeg, 1)]);
+    }
+
+    #[test]
+    fn collects_every_source() {
+        let fs = fixture("rk3588");
+        let mut collector = VideoCollector::discover(&fs, &discover_devfreq_devices(&fs), &ClockTree::new(&fs)).unwrap();
+        assert_eq!(collector.clocks, ["aclk_rkvdec0", "clk_rkvdec0_core", "aclk_rkvenc0", "clk_rkvenc0_core", "aclk_jpeg_decoder", "aclk_jpeg_encoder0"]);
+
+        let mut samples = Vec::new();
+        collector.collect(&fs, &mut samples);
+        assert_eq!(value(&samples, r#"video_load{device="fdc38100.rkvdec-core",block="RKVDEC"}"#), 41.0);
+        assert_eq!(value(&samples, r#"video_frequency{device="fdbd0000.rkvenc-core",block="RKVENC"}"#), 800_000_000.0);
+        assert_eq!(value(&samples, r#"video_load{device="rkvenc-core0",block="RKVENC"}"#), 18.0);
+        assert_eq!(value(&samples, r#"video_sessions{block="RKVDEC"}"#), 2.0);
+        assert_eq!(value(&samples, r#"video_clock{clock="clk_rkvdec0_core"}"#), 600_000_000.0);
+
+        let fs = fixture("rk3528");
+        assert!(VideoCollector::discover(&fs, &[], &ClockTree::new(&fs)).is_none());
+    }
+}
@@ -0,0 +1,191 @@
+//! `--headless` mode: samples with the same collectors as the TUI and writes
+//! one record per interval, for soak tests over SSH or under systemd.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::thread;
+use std::time::Instant;
+use chrono::SecondsFormat;
+
+use crate::cli::{Cli, Format};
+use crate::collector::Registry;
+use crate::metrics::SystemMetrics;
+use crate::session::SessionWriter;
+
+pub fn run(cli: &Cli, registry: Registry, device_info: &str) -> io::Result<()> {
+    let out: Box<dyn Write> = match &cli.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    match log(cli, registry, device_info, out) {
+        // The reader went away, e.g. `cpu_monitor --headless | head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn log(cli: &Cli, mut registry: Registry, device_info: &str, mut out: Box<dyn Write>) -> io::Result<()> {
+    let start = Instant::now();
+    let mut writer = RecordWriter::new(cli.format);
+    let mut recorder = match &cli.record {
+        Some(path) => Some(SessionWriter::create(path, &registry, device_info)?),
+        None => None,
+    };
+    let mut written = 0u64;
+
+    // Interval-based readings such as CPU usage need one snapshot to diff against
+    registry.collect();
+    let mut next = start + cli.interval;
+
+    loop {
+        if cli.samples.is_some_and(|samples| written >= samples)
+            || cli.duration.is_some_and(|duration| next - start > duration)
+        {
+            return out.flush();
+        }
+
+        // Sleep to the next tick rather than for a fixed time, so the collectors' own cost doesn't add drift
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+        next += cli.interval;
+
+        let metrics = registry.collect();
+        writer.write(&mut out, &metrics)?;
+        if let Some(recorder) = &mut recorder {
+            recorder.write(&metrics)?;
+        }
+        // Flush every record so `tail -f` and a killed process see complete lines
+        out.flush()?;
+        written += 1;
+    }
+}
+
+struct RecordWriter {
+    format: Format,
+    /// CSV columns, fixed by the first record.
+    columns: Option<Vec<String>>,
+}
+
+impl RecordWriter {
+    fn new(format: Format) -> RecordWriter {
+        RecordWriter { format, columns: None }
+    }
+
+    fn write(&mut self, out: &mut dyn Write, metrics: &SystemMetrics) -> io::Result<()> {
+        let timestamp = metrics.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false);
+        match self.format {
+            Format::Csv => {
+                // Columns are fixed by the first record: series that only show up later
+                // are not logged, ones that disappear leave their column empty. Top-N
+                // series such as the busiest processes rotate, so they get no column.
+                if self.columns.is_none() {
+                    let columns: Vec<String> = metrics.samples.iter()
+                        .filter(|sample| !sample.series.top_n)
+                        .map(|sample| sample.series.key().to_string())
+                        .collect();
+                    let header: Vec<String> = columns.iter().map(|key| csv_field(key)).collect();
+                    writeln!(out, "timestamp,{}", header.join(","))?;
+                    self.columns = Some(columns);
+                }
+                let values: Vec<String> = self.columns.iter().flatten()
+                    .map(|key| metrics.get(key).map(|sample| sample.value.to_string()).unwrap_or_default())
+                    .collect();
+                writeln!(out, "{},{}", timestamp, values.join(","))
+            }
+            Format::Json => {
+                let mut record = format!("{{\"timestamp\":{}", json_string(&timestamp));
+                for sample in &metrics.samples {
+                    let value = if sample.value.is_finite() { sample.value.to_string() } else { "null".to_string() };
+                    record.push_str(&format!(",{}:{}", json_string(sample.series.key()), value));
+                }
+                writeln!(out, "{}}}", record)
+            }
+        }
+    }
+}
+
+/// Quotes a CSV field if needed. Series keys contain both commas and quotes.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use crate::metrics::{Sample, Series, Unit};
+
+    fn record(values: &[(Series, f64)]) -> SystemMetrics {
+        SystemMetrics::new(
+            Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            values.iter().map(|(series, value)| Sample::new(series.clone(), *value)).collect(),
+        )
+    }
+
+    fn write_all(format: Format, records: &[SystemMetrics]) -> Vec<String> {
+        let mut writer = RecordWriter::new(format);
+        let mut out = Vec::new();
+        for metrics in records {
+            writer.write(&mut out, metrics).unwrap();
+        }
+        String::from_utf8(out).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn csv_keeps_the_first_header() {
+        let usage = Series::new("cpu_usage", Unit::Percent);
+        let core = Series::new("cpu_core_usage", Unit::Percent).label("cluster", "policy0").label("core", 0);
+        let lines = write_all(Format::Csv, &[
+            record(&[(usage.clone(), 12.5), (core.clone(), 50.0)]),
+            record(&[(core, 25.0), (Series::new("npu_usage", Unit::Percent), 1.0)]),
+        ]);
+
+        assert_eq!(lines[0], r#"timestamp,cpu_usage,"cpu_core_usage{cluster=""policy0"",core=""0""}""#);
+        assert!(lines[1].ends_with(",12.5,50"));
+        assert!(lines[2].ends_with(",,25"));
+    }
+
+    #[test]
+    fn csv_leaves_out_top_n_series() {
+        let lines = write_all(Format::Csv, &[record(&[
+            (Series::new("processes", Unit::Count), 120.0),
+            (Series::new("process_cpu", Unit::Percent).label("pid", 1534).top_n(), 80.0),
+        ])]);
+        assert_eq!(lines[0], "timestamp,processes");
+        assert_eq!(lines[1].split(',').skip(1).collect::<Vec<_>>(), ["120"]);
+    }
+
+    #[test]
+    fn json_escapes_keys() {
+        let lines = write_all(Format::Json, &[record(&[
+            (Series::new("npu_core_usage", Unit::Percent).label("core", 1), 35.0),
+            (Series::new("temperature", Unit::Celsius), f64::NAN),
+        ])]);
+
+        let timestamp = Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap().to_rfc3339_opts(SecondsFormat::Millis, false);
+        assert_eq!(
+            lines[0],
+            format!(r#"{{"timestamp":"{}","npu_core_usage{{core=\"1\"}}":35,"temperature":null}}"#, timestamp),
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Unit a sample is recorded in. Values are stored in the base unit and only
+/// scaled for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    Percent,
+    Hertz,
+    Celsius,
+    Count,
+    Rpm,
+    Volt,
+    Ampere,
+    Watt,
+    /// Energy, shown in watt-hours.
+    Joule,
+    /// Memory sizes, shown in MiB.
+    Byte,
+    /// Throughput, shown in MiB/s.
+    ByteRate,
+    /// Events per second, such as packets or I/O operations.
+    PerSecond,
+    /// Durations such as I/O latency, shown in milliseconds.
+    Second,
+}
+
+impl Unit {
+    /// Divisor and suffix used when showing a value of this unit.
+    pub fn display_scale(&self) -> (f64, &'static str) {
+        match self {
+            Unit::Percent => (1.0, "%"),
+            Unit::Hertz => (1_000_000.0, "MHz"),
+            Unit::Celsius => (1.0, "°C"),
+            Unit::Count => (1.0, ""),
+            Unit::Rpm => (1.0, "RPM"),
+            Unit::Volt => (1.0, "V"),
+            Unit::Ampere => (1.0, "A"),
+            Unit::Watt => (1.0, "W"),
+            Unit::Joule => (3600.0, "Wh"),
+            Unit::Byte => (1_048_576.0, "MiB"),
+            Unit::ByteRate => (1_048_576.0, "MiB/s"),
+            Unit::PerSecond => (1.0, "/s"),
+            Unit::Second => (0.001, "ms"),
+        }
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        let (divisor, suffix) = self.display_scale();
+        match self {
+            Unit::Percent | Unit::Celsius => format!("{:.1}{}", value / divisor, suffix),
+            Unit::Count => format!("{:.0}", value / divisor),
+            Unit::Hertz | Unit::Rpm => format!("{:.0} {}", value / divisor, suffix),
+            Unit::Volt | Unit::Ampere => format!("{:.3} {}", value / divisor, suffix),
+            Unit::Watt => format!("{:.2} {}", value / divisor, suffix),
+            Unit::Joule => format!("{:.4} {}", value / divisor, suffix),
+            Unit::Byte => format!("{:.1} {}", value / divisor, suffix),
+            Unit::ByteRate => format!("{:.2} {}", value / divisor, suffix),
+            Unit::PerSecond => format!("{:.1}{}", value / divisor, suffix),
+            Unit::Second => format!("{:.1} {}", value / divisor, suffix),
+        }
+    }
+}
+
+/// How the values of a series relate over time, which decides how it is exported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Kind {
+    /// A reading that can go up and down, such as a temperature.
+    #[default]
+    Gauge,
+    /// A running total that only grows, such as energy used.
+    Counter,
+    /// The change of a counter over the last interval, which depends on the
+    /// interval length. Charted and logged, but not exported to Prometheus,
+    /// which gets the counter itself.
+    Delta,
+}
+
+/// Identity of a time series: a metric name plus labels telling instances
+/// apart, e.g. `cpu_core_usage{core="3"}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub metric: String,
+    pub labels: Vec<(String, String)>,
+    /// Short human readable name used in legends and the status bar. State
+    /// that changes now and then, such as a cpufreq governor or a link speed,
+    /// goes here rather than into the labels, so that a change doesn't cut
+    /// the history into two series.
+    pub name: String,
+    pub unit: Unit,
+    /// Title of the collector that emitted the series, filled in by the registry.
+    pub source: String,
+    pub kind: Kind,
+    /// The series follows whatever is busiest, e.g. the top five processes, so
+    /// it comes and goes. Charted, but left out of CSV columns and Prometheus.
+    pub top_n: bool,
+    key: String,
+}
+
+impl Series {
+    pub fn new(metric: &str, unit: Unit) -> Series {
+        Series {
+            metric: metric.to_string(),
+            labels: Vec::new(),
+            name: metric.to_string(),
+            unit,
+            source: String::new(),
+            kind: Kind::Gauge,
+            top_n: false,
+            key: metric.to_string(),
+        }
+    }
+
+    pub fn label(mut self, key: &str, value: impl fmt::Display) -> Series {
+        self.labels.push((key.to_string(), value.to_string()));
+        self.key = format_key(&self.metric, &self.labels);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Series {
+        self.name = name.into();
+        self
+    }
+
+    pub fn kind(mut self, kind: Kind) -> Series {
+        self.kind = kind;
+        self
+    }
+
+    pub fn top_n(mut self) -> Series {
+        self.top_n = true;
+        self
+    }
+
+    /// Unique key of the series, `metric{label="value",...}`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn label_value(&self, key: &str) -> Option<&str> {
+        self.labels.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+fn format_key(metric: &str, labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return metric.to_string();
+    }
+    let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, v)).collect();
+    format!("{}{{{}}}", metric, labels.join(","))
+}
+
+/// One reading of a series.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub series: Arc<Series>,
+    pub value: f64,
+}
+
+impl Sample {
+    pub fn new(series: Series, value: f64) -> Sample {
+        Sample { series: Arc::new(series), value }
+    }
+}
+
+/// Everything the registered collectors reported in one interval.
+#[derive(Default, Debug, Clone)]
+pub struct SystemMetrics {
+    pub timestamp: DateTime<Local>,
+    /// Samples in the order they were collected. Build with `new` so that
+    /// `get` can find them.
+    pub samples: Vec<Sample>,
+    /// Position of each sample by series key, since charts look up every
+    /// series in every entry of the history on each draw.
+    index: HashMap<String, usize>,
+}
+
+impl SystemMetrics {
+    pub fn new(timestamp: DateTime<Local>, samples: Vec<Sample>) -> SystemMetrics {
+        let index = samples.iter().enumerate().map(|(i, sample)| (sample.series.key().to_string(), i)).collect();
+        SystemMetrics { timestamp, samples, index }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Sample> {
+        self.index.get(key).and_then(|i| self.samples.get(*i))
+    }
+}
@@ -0,0 +1,195 @@
+//! `--serve` mode: samples in the background and exposes the latest interval
+//! as Prometheus text-format metrics on `/metrics`.
+
+use std::fmt::Write as _;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Response, Server};
+
+use crate::collector::Registry;
+use crate::metrics::{Kind, SystemMetrics, Unit};
+
+const PREFIX: &str = "cpu_monitor_";
+
+pub fn serve(address: &str, interval: Duration, mut registry: Registry, device_info: &str) -> io::Result<()> {
+    let server = Server::http(address).map_err(|err| io::Error::other(format!("can't listen on {}: {}", address, err)))?;
+    eprintln!("Serving metrics on http://{}/metrics", address);
+
+    // Interval-based readings such as CPU usage need one snapshot to diff
+    // against; the page starts with the readings that don't
+    let page = Arc::new(Mutex::new(render(&registry.collect(), device_info)));
+
+    // Scrapes are answered from the last rendered interval, so they never wait on sysfs
+    let shared = page.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match request.url() {
+                "/metrics" => {
+                    let body = shared.lock().map(|page| page.clone()).unwrap_or_default();
+                    let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4; charset=utf-8");
+                    Response::from_string(body).with_header(content_type.expect("static header"))
+                }
+                "/" => Response::from_string("cpu_monitor exporter, metrics at /metrics\n"),
+                _ => Response::from_string("not found\n").with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    let mut next = Instant::now() + interval;
+    loop {
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+        next += interval;
+
+        let rendered = render(&registry.collect(), device_info);
+        if let Ok(mut page) = page.lock() {
+            *page = rendered;
+        }
+    }
+}
+
+/// Prometheus name suffix and the divisor from the recorded value to the
+/// exported one. Percentages are exported as 0-1 ratios.
+fn export_unit(unit: Unit) -> (&'static str, f64) {
+    match unit {
+        Unit::Percent => ("_ratio", 100.0),
+        Unit::Hertz => ("_hertz", 1.0),
+        Unit::Celsius => ("_celsius", 1.0),
+        Unit::Rpm => ("_rpm", 1.0),
+        Unit::Volt => ("_volts", 1.0),
+        Unit::Ampere => ("_amperes", 1.0),
+        Unit::Watt => ("_watts", 1.0),
+        Unit::Joule => ("_joules", 1.0),
+        Unit::Byte => ("_bytes", 1.0),
+        Unit::ByteRate => ("_bytes_per_second", 1.0),
+        Unit::PerSecond => ("_per_second", 1.0),
+        Unit::Second => ("_seconds", 1.0),
+        Unit::Count => ("", 1.0),
+    }
+}
+
+/// Renders one interval as gauges and counters, one family per metric and
+/// unit, so a metric recorded in two units can't be scaled by the wrong one.
+/// Interval deltas are left out in favour of their counters, and so are top-N
+/// series, since every change in the top five would start new time series.
+/// Every series also carries the device-tree `compatible` string so a fleet
+/// can be told apart.
+fn render(metrics: &SystemMetrics, device_info: &str) -> String {
+    let mut out = String::new();
+    let mut families: Vec<(&str, Unit, Kind)> = Vec::new();
+    for sample in &metrics.samples {
+        let series = &sample.series;
+        let family = (series.metric.as_str(), series.unit, series.kind);
+        if series.kind != Kind::Delta && !series.top_n && !families.contains(&family) {
+            families.push(family);
+        }
+    }
+
+    let _ = writeln!(out, "# HELP {}device_info Device-tree compatible string of the board.", PREFIX);
+    let _ = writeln!(out, "# TYPE {}device_info gauge", PREFIX);
+    let _ = writeln!(out, "{}device_info{{compatible=\"{}\"}} 1", PREFIX, escape(device_info));
+
+    for (family, unit, kind) in families {
+        let mut samples = metrics.samples.iter()
+            .filter(|sample| sample.series.metric == family && sample.series.unit == unit && sample.series.kind == kind)
+            .peekable();
+        let Some(first) = samples.peek() else {
+            continue;
+        };
+        let (suffix, divisor) = export_unit(unit);
+        let counter = kind == Kind::Counter;
+        let name = format!("{}{}{}{}", PREFIX, family, suffix, if counter { "_total" } else { "" });
+        let _ = writeln!(out, "# HELP {} {} metric of the {} collector.", name, family, first.series.source);
+        let _ = writeln!(out, "# TYPE {} {}", name, if counter { "counter" } else { "gauge" });
+
+        for sample in samples {
+            let mut labels: Vec<String> = sample.series.labels.iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+                .collect();
+            labels.push(format!("compatible=\"{}\"", escape(device_info)));
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), format_value(sample.value / divisor));
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::default_registry;
+    use crate::metrics::{Sample, Series};
+    use crate::sysfs::fixture;
+
+    #[test]
+    fn renders_units_and_labels() {
+        let mut registry = default_registry(Box::new(fixture("rk3588")));
+        // CPU usage needs a snapshot to diff against
+        registry.collect();
+        let page = render(&registry.collect(), "radxa,rock-5b, rockchip,rk3588");
+        let line = |prefix: &str| page.lines().find(|line| line.starts_with(prefix)).unwrap_or_else(|| panic!("no {}", prefix));
+
+        assert_eq!(
+            line("cpu_monitor_device_info"),
+            r#"cpu_monitor_device_info{compatible="radxa,rock-5b, rockchip,rk3588"} 1"#,
+        );
+        assert_eq!(
+            line("cpu_monitor_cpu_core_frequency_hertz{cluster=\"policy4\",core=\"5\""),
+            r#"cpu_monitor_cpu_core_frequency_hertz{cluster="policy4",core="5",compatible="radxa,rock-5b, rockchip,rk3588"} 2352000000"#,
+        );
+        assert!(line("cpu_monitor_npu_core_usage_ratio{core=\"0\"").ends_with("} 0.35"));
+        assert!(line("cpu_monitor_temperature_celsius{").ends_with("} 41.666"));
+        assert!(line("cpu_monitor_cooling_state{device=\"cooling_device4\",type=\"pwm-fan\"").ends_with("} 1"));
+        assert!(page.contains("# TYPE cpu_monitor_gpu_usage_ratio gauge\n"));
+
+        // Each family is declared once, before all of its samples
+        assert_eq!(page.matches("# TYPE cpu_monitor_cpu_core_usage_ratio gauge").count(), 1);
+    }
+
+    #[test]
+    fn exports_counters_and_leaves_out_top_n() {
+        let mut registry = default_registry(Box::new(fixture("rk3588")));
+        // The second interval has the stall deltas that are left out
+        registry.collect();
+        let page = render(&registry.collect(), "rk3588");
+        assert!(page.contains("# TYPE cpu_monitor_energy_joules_total counter\n"));
+        assert!(page.contains("cpu_monitor_energy_joules_total{rail="));
+        assert!(!page.contains("cpu_monitor_process_cpu"));
+        assert!(!page.contains("cpu_monitor_cgroup_memory"));
+        assert!(page.contains("# TYPE cpu_monitor_processes gauge\n"));
+        assert!(page.contains("cpu_monitor_psi_stall_seconds_total{resource=\"io\",kind=\"some\",compatible=\"rk3588\"} 120.331009\n"));
+        assert!(!page.contains("psi_stall_delta"));
+    }
+
+    #[test]
+    fn splits_families_by_unit() {
+        let metrics = SystemMetrics::new(chrono::Local::now(), vec![
+            Sample::new(Series::new("mixed", Unit::Percent).label("id", "a"), 50.0),
+            Sample::new(Series::new("mixed", Unit::Hertz).label("id", "b"), 1000.0),
+        ]);
+        let page = render(&metrics, "board");
+        assert!(page.contains("cpu_monitor_mixed_ratio{id=\"a\",compatible=\"board\"} 0.5\n"));
+        assert!(page.contains("cpu_monitor_mixed_hertz{id=\"b\",compatible=\"board\"} 1000\n"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+        assert_eq!(format_value(f64::NAN), "NaN");
+    }
+}
@@ -0,0 +1,439 @@
+//! Session files: samples recorded with `--record` and shown again with
+//! `--replay`, e.g. to inspect throttling captured on a customer board.
+//!
+//! A file starts with a magic line and a header carrying the device info and
+//! every tab's layout, so the replay looks like the live UI without the
+//! board's sysfs. After that comes a stream of records: each series is
+//! introduced once and then referred to by index, and every frame only stores
+//! the values that changed since the previous one.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use bincode::Options;
+use chrono::{DateTime, Local, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::collector::{ChartSpec, Collector, Registry, SeriesFilter};
+use crate::metrics::{Sample, Series, SystemMetrics, Unit};
+use crate::sysfs::SysFs;
+
+const MAGIC: &[u8] = b"cpu_monitor session 4\n";
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    device_info: String,
+    /// Milliseconds since the epoch that the first frame's delta is relative to.
+    start: i64,
+    tabs: Vec<RecordedTab>,
+}
+
+/// Name, layout and summary of one collector, as they were when recording.
+#[derive(Serialize, Deserialize)]
+struct RecordedTab {
+    name: String,
+    layout: Vec<Vec<ChartSpec>>,
+    summary: Vec<SeriesFilter>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Record {
+    /// Introduces the next series index.
+    Series {
+        metric: String,
+        labels: Vec<(String, String)>,
+        name: String,
+        unit: Unit,
+        source: String,
+    },
+    /// One interval. `delta` is the time since the previous frame in milliseconds.
+    Frame {
+        delta: i64,
+        changed: Vec<(u32, f64)>,
+        removed: Vec<u32>,
+    },
+}
+
+fn encoding() -> impl Options {
+    // Varints keep indices and time deltas to a byte or two
+    bincode::DefaultOptions::new()
+}
+
+fn invalid(err: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Appends every collected interval to a session file.
+pub struct SessionWriter<W: Write> {
+    out: W,
+    last_timestamp: i64,
+    /// Index of every series written so far, by key.
+    indices: HashMap<String, u32>,
+    /// Values of the previous frame, by index.
+    previous: HashMap<u32, f64>,
+}
+
+impl SessionWriter<BufWriter<File>> {
+    pub fn create(path: &Path, registry: &Registry, device_info: &str) -> io::Result<Self> {
+        SessionWriter::new(BufWriter::new(File::create(path)?), registry, device_info)
+    }
+}
+
+impl<W: Write> SessionWriter<W> {
+    pub fn new(mut out: W, registry: &Registry, device_info: &str) -> io::Result<Self> {
+        let start = Local::now().timestamp_millis();
+        let header = Header {
+            device_info: device_info.to_string(),
+            start,
+            tabs: registry.collectors().iter()
+                .map(|collector| RecordedTab {
+                    name: collector.name().to_string(),
+                    layout: collector.layout(),
+                    summary: collector.summary(),
+                })
+                .collect(),
+        };
+        out.write_all(MAGIC)?;
+        encoding().serialize_into(&mut out, &header).map_err(invalid)?;
+        Ok(SessionWriter { out, last_timestamp: start, indices: HashMap::new(), previous: HashMap::new() })
+    }
+
+    pub fn write(&mut self, metrics: &SystemMetrics) -> io::Result<()> {
+        let mut current = HashMap::new();
+        let mut changed = Vec::new();
+
+        for sample in &metrics.samples {
+            let series = &sample.series;
+            let index = match self.indices.get(series.key()) {
+                Some(index) => *index,
+                None => {
+                    let index = self.indices.len() as u32;
+                    self.record(&Record::Series {
+                        metric: series.metric.clone(),
+                        labels: series.labels.clone(),
+                        name: series.name.clone(),
+                        unit: series.unit,
+                        source: series.source.clone(),
+                    })?;
+                    self.indices.insert(series.key().to_string(), index);
+                    index
+                }
+            };
+            // Compare bits so a NaN that stays NaN is not written again
+            if self.previous.get(&index).map(|value| value.to_bits()) != Some(sample.value.to_bits()) {
+                changed.push((index, sample.value));
+            }
+            current.insert(index, sample.value);
+        }
+
+        let mut removed: Vec<u32> = self.previous.keys().filter(|index| !current.contains_key(index)).copied().collect();
+        removed.sort_unstable();
+
+        let timestamp = metrics.timestamp.timestamp_millis();
+        self.record(&Record::Frame { delta: timestamp - self.last_timestamp, changed, removed })?;
+        self.last_timestamp = timestamp;
+        self.previous = current;
+
+        // Flush every frame so a board that loses power still leaves a usable file
+        self.out.flush()
+    }
+
+    fn record(&mut self, record: &Record) -> io::Result<()> {
+        encoding().serialize_into(&mut self.out, record).map_err(invalid)
+    }
+}
+
+/// A recorded session, fully loaded.
+pub struct Session {
+    pub device_info: String,
+    tabs: Vec<RecordedTab>,
+    pub frames: Vec<SystemMetrics>,
+}
+
+impl Session {
+    /// Reads a session to replay, which needs at least one tab and one frame.
+    pub fn open(path: &Path) -> io::Result<Session> {
+        let session = Session::read(BufReader::new(File::open(path)?))?;
+        if session.tabs.is_empty() || session.frames.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the session has no recorded samples"));
+        }
+        Ok(session)
+    }
+
+    pub fn read(mut input: impl Read) -> io::Result<Session> {
+        let mut magic = vec![0; MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            let message = if magic.starts_with(b"cpu_monitor session ") {
+                "session file written by an incompatible version of cpu_monitor"
+            } else {
+                "not a cpu_monitor session file"
+            };
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        let header: Header = encoding().deserialize_from(&mut input).map_err(invalid)?;
+
+        let mut series: Vec<Arc<Series>> = Vec::new();
+        let mut values: Vec<Option<f64>> = Vec::new();
+        let mut timestamp = header.start;
+        let mut frames = Vec::new();
+
+        // A recording cut short ends in a partial record, which is dropped
+        while let Ok(record) = encoding().deserialize_from::<_, Record>(&mut input) {
+            match record {
+                Record::Series { metric, labels, name, unit, source } => {
+                    let mut recorded = labels.iter()
+                        .fold(Series::new(&metric, unit), |recorded, (key, value)| recorded.label(key, value))
+                        .name(name);
+                    recorded.source = source;
+                    series.push(Arc::new(recorded));
+                    values.push(None);
+                }
+                Record::Frame { delta, changed, removed } => {
+                    for (index, value) in changed {
+                        if let Some(slot) = values.get_mut(index as usize) {
+                            *slot = Some(value);
+                        }
+                    }
+                    for index in removed {
+                        if let Some(slot) = values.get_mut(index as usize) {
+                            *slot = None;
+                        }
+                    }
+                    timestamp += delta;
+                    let Some(time) = Local.timestamp_millis_opt(timestamp).single() else {
+                        continue;
+                    };
+                    frames.push(SystemMetrics::new(
+                        time,
+                        series.iter().zip(&values)
+                            .filter_map(|(series, value)| value.map(|value| Sample { series: series.clone(), value }))
+                            .collect(),
+                    ));
+                }
+            }
+        }
+
+        Ok(Session { device_info: header.device_info, tabs: header.tabs, frames })
+    }
+
+    /// A registry with one tab per recorded collector, for drawing the replay.
+    /// Its collectors never produce samples.
+    pub fn registry(&self, fs: Box<dyn SysFs>) -> Registry {
+        let mut registry = Registry::new(fs);
+        for tab in &self.tabs {
+            registry.register(RecordedCollector {
+                name: tab.name.clone(),
+                layout: tab.layout.clone(),
+                summary: tab.summary.clone(),
+            });
+        }
+        registry
+    }
+}
+
+struct RecordedCollector {
+    name: String,
+    layout: Vec<Vec<ChartSpec>>,
+    summary: Vec<SeriesFilter>,
+}
+
+impl Collector for RecordedCollector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn collect(&mut self, _fs: &dyn SysFs, _samples: &mut Vec<Sample>) {}
+
+    fn layout(&self) -> Vec<Vec<ChartSpec>> {
+        self.layout.clone()
+    }
+
+    fn summary(&self) -> Vec<SeriesFilter> {
+        self.summary.clone()
+    }
+}
+
+/// Playback position in a session, advanced in real time times `speed`.
+pub struct Player {
+    frames: Vec<SystemMetrics>,
+    position: DateTime<Local>,
+    pub playing: bool,
+    pub speed: f64,
+    last_tick: Instant,
+}
+
+impl Player {
+    const SEEK_STEP: Duration = Duration::from_secs(10);
+
+    pub fn new(frames: Vec<SystemMetrics>) -> Player {
+        let position = frames.first().map(|frame| frame.timestamp).unwrap_or_default();
+        Player { frames, position, playing: true, speed: 1.0, last_tick: Instant::now() }
+    }
+
+    fn start(&self) -> DateTime<Local> {
+        self.frames.first().map(|frame| frame.timestamp).unwrap_or(self.position)
+    }
+
+    fn end(&self) -> DateTime<Local> {
+        self.frames.last().map(|frame| frame.timestamp).unwrap_or(self.position)
+    }
+
+    /// Time from the start of the recording to the playback position, and its total length.
+    pub fn progress(&self) -> (TimeDelta, TimeDelta) {
+        (self.position - self.start(), self.end() - self.start())
+    }
+
+    /// Moves the position forward by the wall time since the last tick. Playback
+    /// pauses at the end of the recording.
+    pub fn tick(&mut self) {
+        let elapsed = self.last_tick.elapsed();
+        self.last_tick = Instant::now();
+        if self.playing {
+            self.seek_by(TimeDelta::from_std(elapsed.mul_f64(self.speed)).unwrap_or(TimeDelta::MAX));
+            if self.position >= self.end() {
+                self.playing = false;
+            }
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        // Playing again from the end starts over
+        if !self.playing && self.position >= self.end() {
+            self.position = self.start();
+        }
+        self.playing = !self.playing;
+    }
+
+    pub fn seek_forward(&mut self) {
+        self.seek_by(TimeDelta::from_std(Self::SEEK_STEP).unwrap_or_default());
+    }
+
+    pub fn seek_backward(&mut self) {
+        self.seek_by(-TimeDelta::from_std(Self::SEEK_STEP).unwrap_or_default());
+    }
+
+    pub fn seek_to_start(&mut self) {
+        self.position = self.start();
+    }
+
+    pub fn seek_to_end(&mut self) {
+        self.position = self.end();
+    }
+
+    fn seek_by(&mut self, delta: TimeDelta) {
+        let position = self.position.checked_add_signed(delta).unwrap_or(self.position);
+        self.position = position.clamp(self.start(), self.end());
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(64.0);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(0.125);
+    }
+
+    /// Frames of the last `history` up to the playback position, from the
+    /// first frame when the history reaches back past what chrono can represent.
+    pub fn window(&self, history: Duration) -> &[SystemMetrics] {
+        let start = match TimeDelta::from_std(history).ok().and_then(|history| self.position.checked_sub_signed(history)) {
+            Some(oldest) => self.frames.partition_point(|frame| frame.timestamp < oldest),
+            None => 0,
+        };
+        let end = self.frames.partition_point(|frame| frame.timestamp <= self.position);
+        &self.frames[start..end.max(start)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture;
+    use crate::collectors::default_registry;
+
+    fn frame(registry: &mut Registry, at: i64) -> SystemMetrics {
+        let mut metrics = registry.collect();
+        metrics.timestamp = Local.timestamp_millis_opt(at).unwrap();
+        metrics
+    }
+
+    #[test]
+    fn round_trips_frames() {
+        let mut registry = default_registry(Box::new(fixture("rk3588")));
+        let start = Local::now().timestamp_millis();
+        let mut frames = vec![frame(&mut registry, start + 200), frame(&mut registry, start + 400)];
+        // A series that disappears, and one that only shows up later
+        let mut samples = std::mem::take(&mut frames[1].samples);
+        samples.retain(|sample| sample.series.metric != "swap_usage");
+        samples.push(Sample::new(Series::new("npu_core_usage", Unit::Percent).label("core", 3), 7.0));
+        frames[1] = SystemMetrics::new(frames[1].timestamp, samples);
+
+        let mut file = Vec::new();
+        let mut writer = SessionWriter::new(&mut file, &registry, "radxa,rock-5b, rockchip,rk3588").unwrap();
+        for metrics in &frames {
+            writer.write(metrics).unwrap();
+        }
+
+        let session = Session::read(file.as_slice()).unwrap();
+        assert_eq!(session.device_info, "radxa,rock-5b, rockchip,rk3588");
+        assert_eq!(session.frames.len(), 2);
+        for (read, written) in session.frames.iter().zip(&frames) {
+            assert_eq!(read.timestamp, written.timestamp);
+            let mut keys: Vec<_> = read.samples.iter().map(|sample| (sample.series.key(), sample.value)).collect();
+            let mut expected: Vec<_> = written.samples.iter().map(|sample| (sample.series.key(), sample.value)).collect();
+            keys.sort_by(|a, b| a.0.cmp(b.0));
+            expected.sort_by(|a, b| a.0.cmp(b.0));
+            assert_eq!(keys, expected);
+        }
+        assert_eq!(session.frames[0].get(r#"temperature{zone="thermal_zone0",type="soc-thermal"}"#).unwrap().series.source, "Thermal");
+
+        let replay = session.registry(Box::new(fixture("rk3588")));
+        let tabs: Vec<_> = replay.collectors().iter().map(|collector| collector.name()).collect();
+        assert_eq!(tabs, ["CPU", "GPU", "NPU", "RGA", "Video", "Memory", "Processes", "Network", "Disk", "Pressure", "Containers", "Thermal", "Cooling", "Hwmon", "Power", "Devfreq", "Clocks"]);
+        assert_eq!(replay.layout(0, &session.frames[0]).len(), 4);
+    }
+
+    #[test]
+    fn drops_a_truncated_frame() {
+        let mut registry = default_registry(Box::new(fixture("rk3566")));
+        let start = Local::now().timestamp_millis();
+        let mut file = Vec::new();
+        let mut writer = SessionWriter::new(&mut file, &registry, "").unwrap();
+        writer.write(&frame(&mut registry, start)).unwrap();
+        let mut last = frame(&mut registry, start + 200);
+        last.samples.iter_mut().for_each(|sample| sample.value += 1.0);
+        writer.write(&last).unwrap();
+
+        file.truncate(file.len() - 3);
+        assert_eq!(Session::read(file.as_slice()).unwrap().frames.len(), 1);
+        assert!(Session::read(&b"not a session"[..]).is_err());
+    }
+
+    #[test]
+    fn player_windows_and_seeks() {
+        let frames: Vec<SystemMetrics> = (0..100)
+            .map(|i| SystemMetrics::new(Local.timestamp_millis_opt(i * 1000).unwrap(), Vec::new()))
+            .collect();
+        let mut player = Player::new(frames);
+        assert_eq!(player.window(Duration::from_secs(30)).len(), 1);
+
+        player.seek_forward();
+        player.seek_forward();
+        assert_eq!(player.progress().0, TimeDelta::seconds(20));
+        assert_eq!(player.window(Duration::from_secs(5)).len(), 6);
+
+        player.seek_to_end();
+        player.seek_forward();
+        assert_eq!(player.progress(), (TimeDelta::seconds(99), TimeDelta::seconds(99)));
+        assert_eq!(player.window(Duration::from_secs(10)).last().unwrap().timestamp.timestamp(), 99);
+        assert_eq!(player.window(Duration::MAX).len(), 100);
+
+        player.playing = false;
+        player.toggle_pause();
+        assert_eq!(player.progress().0, TimeDelta::zero());
+    }
+}
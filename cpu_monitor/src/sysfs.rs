@@ -0,0 +1,122 @@
+//! Filesystem access for the collectors. Paths are always written as on the
+//! target (`/proc/stat`, `/sys/class/devfreq`) and resolved against a root, so
+//! the same code runs on a board or against a directory of captured files.
+
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStringExt;
+use std::path::PathBuf;
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use crate::metrics::Sample;
+
+/// Size of a mounted filesystem, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FsSpace {
+    pub total: u64,
+    pub free: u64,
+    /// Free space usable by unprivileged users, which excludes the root reserve.
+    pub available: u64,
+}
+
+pub trait SysFs {
+    /// Contents of the file, or `None` if it is missing or unreadable.
+    fn read_to_string(&self, path: &str) -> Option<String>;
+
+    /// Names of the entries in the directory, sorted. Empty if it can't be read.
+    fn read_dir(&self, path: &str) -> Vec<String>;
+
+    /// Target of a symlink, such as an open file under `/proc/<pid>/fd`.
+    fn read_link(&self, path: &str) -> Option<String>;
+
+    /// `statvfs` of the filesystem holding `path`, or `None` if it fails.
+    fn statvfs(&self, path: &str) -> Option<FsSpace>;
+}
+
+/// Real filesystem below `root`, which is `/` on a live system.
+pub struct RootFs {
+    root: PathBuf,
+}
+
+impl RootFs {
+    pub fn new(root: impl Into<PathBuf>) -> RootFs {
+        RootFs { root: root.into() }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+}
+
+impl SysFs for RootFs {
+    fn read_to_string(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.resolve(path)).ok()
+    }
+
+    fn read_dir(&self, path: &str) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.resolve(path))
+            .map(|entries| entries.flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    fn read_link(&self, path: &str) -> Option<String> {
+        fs::read_link(self.resolve(path)).ok().map(|target| target.to_string_lossy().to_string())
+    }
+
+    // The field types are u64 on 64-bit targets but u32 on 32-bit ARM
+    #[allow(clippy::useless_conversion)]
+    fn statvfs(&self, path: &str) -> Option<FsSpace> {
+        let path = CString::new(self.resolve(path).into_os_string().into_vec()).ok()?;
+        // SAFETY: `path` is NUL-terminated and `stat` is a plain C struct for statvfs to fill in
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        // Block counts are in units of the fragment size
+        let block = u64::from(stat.f_frsize);
+        Some(FsSpace {
+            total: u64::from(stat.f_blocks) * block,
+            free: u64::from(stat.f_bfree) * block,
+            available: u64::from(stat.f_bavail) * block,
+        })
+    }
+}
+
+/// Synthetic board tree under `tests/fixtures/<board>`, see its README.
+#[cfg(test)]
+pub fn fixture(board: &str) -> RootFs {
+    RootFs::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(board))
+}
+
+/// The sample with `key` among those a collector emitted.
+#[cfg(test)]
+pub fn sample<'a>(samples: &'a [Sample], key: &str) -> &'a Sample {
+    samples.iter().find(|sample| sample.series.key() == key).unwrap_or_else(|| panic!("no sample {}", key))
+}
+
+#[cfg(test)]
+pub fn value(samples: &[Sample], key: &str) -> f64 {
+    sample(samples, key).value
+}
+
+/// Reads twice, `elapsed` apart, with `rewind` taking known amounts off the
+/// collector's previous reading in between, so the second read sees those
+/// amounts as the change over the interval. Returns both reads.
+#[cfg(test)]
+pub fn rewound<C, T>(
+    collector: &mut C,
+    elapsed: Duration,
+    mut read: impl FnMut(&mut C, Instant) -> T,
+    rewind: impl FnOnce(&mut C),
+) -> (T, T) {
+    let start = Instant::now();
+    let first = read(collector, start);
+    rewind(collector);
+    (first, read(collector, start + elapsed))
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Dataset, GraphType, List, ListItem, ListState,
+        Paragraph, Tabs
+    },
+    Frame, text::{Line, Span},
+};
+use chrono::TimeDelta;
+
+use crate::app::App;
+use crate::collector::{ChartSpec, Panel};
+use crate::metrics::SystemMetrics;
+
+/// Colors cycled through for the lines of a chart.
+const SERIES_COLORS: [Color; 8] = [
+    Color::Yellow, Color::Green, Color::Cyan, Color::Magenta,
+    Color::Red, Color::Blue, Color::LightGreen, Color::LightRed,
+];
+
+/// Colors of threshold lines, from the lowest threshold up; the highest is always red.
+const THRESHOLD_COLORS: [Color; 3] = [Color::DarkGray, Color::LightRed, Color::Red];
+
+/// Colors of marked intervals, such as throttling.
+const MARK_COLORS: [Color; 2] = [Color::LightRed, Color::LightMagenta];
+
+/// Style with the given foreground, or the terminal's default with `--no-color`.
+fn fg(app: &App, color: Color) -> Style {
+    if app.options.color { Style::default().fg(color) } else { Style::default() }
+}
+
+pub fn draw_ui(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .split(f.size());
+
+    // Title with device info, or the playback state when replaying
+    let title = match &app.player {
+        Some(player) => {
+            let (position, length) = player.progress();
+            format!(
+                "Replay - {} - {} {}x {} / {} - Space pause, [/] seek, -/+ speed, 'q' quit",
+                app.device_info,
+                if player.playing { "▶" } else { "⏸" },
+                player.speed,
+                format_clock(position),
+                format_clock(length),
+            )
+        }
+        None => format!("System Monitor - {} - Press 'q' to quit, ←/→ to switch tabs", app.device_info),
+    };
+    let title = Paragraph::new(title)
+        .style(fg(app, Color::Cyan).add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(title, chunks[0]);
+
+    // Tabs
+    let titles: Vec<ratatui::text::Line> = app.registry.collectors()
+        .iter()
+        .map(|c| Line::from(vec![Span::styled(c.name().to_string(), fg(app, Color::White))]))
+        .collect();
+
+    // Without colors the selected tab is shown in reverse video instead
+    let highlight = if app.options.color { Modifier::BOLD } else { Modifier::BOLD | Modifier::REVERSED };
+    let tabs = Tabs::new(titles)
+        .block(Block::default().borders(Borders::ALL).title("Metrics"))
+        .select(app.current_tab)
+        .style(fg(app, Color::White))
+        .highlight_style(fg(app, Color::Yellow).add_modifier(highlight));
+    f.render_widget(tabs, chunks[1]);
+
+    // Current metrics display with values
+    if let Some(latest) = app.metrics_history().last() {
+        let values: Vec<String> = app.registry.summary(app.current_tab, latest)
+            .iter()
+            .map(|sample| format!("{}: {}", sample.series.name, sample.series.unit.format(sample.value)))
+            .collect();
+        let info_text = format!("{} | Last Update: {}", values.join(" | "), latest.timestamp.format("%H:%M:%S"));
+
+        // Status bar with current values
+        let status = Paragraph::new(info_text)
+            .style(fg(app, Color::Green).add_modifier(Modifier::BOLD))
+            .block(Block::default().borders(Borders::ALL).title("Current Values"));
+        f.render_widget(status, chunks[2]);
+
+        draw_tab(f, app, latest, chunks[3]);
+    } else {
+        // Show message when no data is available yet
+        let no_data = Paragraph::new("Collecting system data...")
+            .style(fg(app, Color::Yellow))
+            .block(Block::default().borders(Borders::ALL).title("Status"));
+        f.render_widget(no_data, chunks[2]);
+    }
+}
+
+/// Lays out the charts of the current tab as rows of equally wide columns,
+/// below the tab's panel if it has one.
+fn draw_tab(f: &mut Frame, app: &App, latest: &SystemMetrics, area: Rect) {
+    let area = match app.registry.panel(app.current_tab) {
+        Some(panel) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(area);
+            draw_panel(f, app, &panel, chunks[0]);
+            chunks[1]
+        }
+        None => area,
+    };
+
+    let layout = app.registry.layout(app.current_tab, latest);
+    if layout.is_empty() {
+        return;
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(layout.iter().map(|_| Constraint::Ratio(1, layout.len() as u32)).collect::<Vec<_>>())
+        .split(area);
+
+    let mut chart_index = 0;
+    for (charts, row) in layout.iter().zip(rows.iter()) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(charts.iter().map(|_| Constraint::Ratio(1, charts.len() as u32)).collect::<Vec<_>>())
+            .split(*row);
+        for (chart, column) in charts.iter().zip(columns.iter()) {
+            if chart.bars {
+                draw_bars(f, app, latest, chart, *column);
+            } else {
+                draw_chart(f, app, latest, chart, *column, chart_index);
+            }
+            chart_index += 1;
+        }
+    }
+}
+
+/// Draws the panel's header and its rows, scrolled to keep the selected row in view.
+fn draw_panel(f: &mut Frame, app: &App, panel: &Panel, area: Rect) {
+    let block = Block::default().title(panel.title.clone()).borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    f.render_widget(Paragraph::new(panel.header.clone()).style(fg(app, Color::Gray).add_modifier(Modifier::BOLD)), chunks[0]);
+
+    let items: Vec<ListItem> = panel.rows.iter().map(|row| ListItem::new(row.clone())).collect();
+    let list = List::new(items).highlight_style(fg(app, Color::Yellow).add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(panel.selected));
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+/// Draws every series matching the spec as one line, every threshold as a
+/// horizontal line at its latest value, and the intervals in which a marked
+/// series was non-zero as a strip along the bottom. Without fixed bounds the
+/// Y axis spans the combined range of all lines and thresholds.
+fn draw_chart(f: &mut Frame, app: &App, latest: &SystemMetrics, spec: &ChartSpec, area: Rect, chart_index: usize) {
+    let mut series: Vec<_> = latest.samples.iter()
+        .map(|sample| &sample.series)
+        .filter(|series| spec.matches(series))
+        .collect();
+    if spec.stacked {
+        series.sort_by_key(|series| spec.position(series));
+    }
+    let mut series_data: Vec<_> = series.iter().map(|series| app.get_data_for_chart(series.key())).collect();
+    let areas = if spec.stacked { stack(&mut series_data) } else { Vec::new() };
+
+    // A single-line chart takes its color from its position on the tab
+    let color_offset = if series.len() == 1 { chart_index } else { 0 };
+    let datasets: Vec<Dataset> = series.iter().zip(&series_data).enumerate()
+        .map(|(i, (series, (data, _)))| Dataset::default()
+            .name(series.name.clone())
+            .marker(if spec.stacked { symbols::Marker::Braille } else { symbols::Marker::Dot })
+            .style(fg(app, SERIES_COLORS[(i + color_offset) % SERIES_COLORS.len()]))
+            .graph_type(GraphType::Line)
+            .data(areas.get(i).unwrap_or(data)))
+        .collect();
+    let history = app.options.history.as_secs_f64();
+
+    // Thresholds span the whole X axis, sorted so the highest gets the strongest color
+    let mut thresholds: Vec<_> = latest.samples.iter()
+        .filter(|sample| spec.matches_threshold(&sample.series))
+        .map(|sample| {
+            let value = sample.value / sample.series.unit.display_scale().0;
+            (sample.series.name.clone(), [(-history, value), (0.0, value)])
+        })
+        .collect();
+    thresholds.sort_by(|a, b| a.1[0].1.total_cmp(&b.1[0].1));
+    let threshold_count = thresholds.len();
+    let mut datasets: Vec<Dataset> = datasets.into_iter()
+        .chain(thresholds.iter().enumerate().map(|(i, (name, line))| {
+            let from_top = threshold_count - 1 - i;
+            let color = THRESHOLD_COLORS[THRESHOLD_COLORS.len().saturating_sub(1 + from_top)];
+            Dataset::default()
+                .name(name.clone())
+                .marker(symbols::Marker::Braille)
+                .style(fg(app, color))
+                .graph_type(GraphType::Line)
+                .data(line)
+        }))
+        .collect();
+
+    let bounds = spec.y_bounds.unwrap_or_else(|| {
+        let (min, max) = series_data.iter()
+            .map(|(_, bounds)| *bounds)
+            .fold((f64::MAX, 0.0_f64), |acc, bounds| (acc.0.min(bounds.0), acc.1.max(bounds.1)));
+        // Leave a little room above the highest threshold so its line isn't drawn on the border
+        let threshold_max = thresholds.iter().map(|(_, line)| line[0].1 * 1.05).fold(max, f64::max);
+        let threshold_min = thresholds.iter().map(|(_, line)| line[0].1 * 0.95).fold(min, f64::min);
+        (threshold_min, threshold_max)
+    });
+    let bottom = bounds.0.min(bounds.1);
+
+    // Each sample covers the time until the next one, filled in steps of a
+    // column so that an interval shows as a solid strip
+    let step = history / area.width.max(1) as f64;
+    let marks: Vec<(String, Vec<(f64, f64)>)> = latest.samples.iter()
+        .filter(|sample| spec.matches_mark(&sample.series))
+        .map(|sample| {
+            let (data, _) = app.get_data_for_chart(sample.series.key());
+            let mut points = Vec::new();
+            for (i, &(start, value)) in data.iter().enumerate() {
+                if value == 0.0 {
+                    continue;
+                }
+                let end = data.get(i + 1).map_or(start, |next| next.0);
+                let mut x = start;
+                while x <= end {
+                    points.push((x, bottom));
+                    x += step;
+                }
+            }
+            (sample.series.name.clone(), points)
+        })
+        .collect();
+    datasets.extend(marks.iter().enumerate().map(|(i, (name, points))| Dataset::default()
+        .name(name.clone())
+        .marker(symbols::Marker::Block)
+        .style(fg(app, MARK_COLORS[i % MARK_COLORS.len()]))
+        .graph_type(GraphType::Scatter)
+        .data(points)));
+    let y_title = series.first()
+        .map(|series| series.unit.display_scale().1)
+        .filter(|suffix| !suffix.is_empty())
+        .unwrap_or("Value");
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(spec.title.clone()).borders(Borders::ALL))
+        // Thresholds make for longer legends, so allow up to half the chart before hiding it
+        .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+        .x_axis(Axis::default()
+            .title("Time")
+            .style(fg(app, Color::Gray))
+            .labels(vec![
+                Span::raw(format!("-{}", format_span(history))),
+                Span::raw(format!("-{}", format_span(history / 2.0))),
+                Span::raw("now"),
+            ])
+            .bounds([-history, 0.0]))
+        .y_axis(Axis::default()
+            .title(y_title)
+            .style(fg(app, Color::Gray))
+            .bounds([bounds.0.min(bounds.1), bounds.1]));
+
+    f.render_widget(chart, area);
+}
+
+/// Draws the latest value of every series matching the spec as a bar,
+/// labelled with the series name, e.g. one bar per frequency.
+fn draw_bars(f: &mut Frame, app: &App, latest: &SystemMetrics, spec: &ChartSpec, area: Rect) {
+    let block = Block::default().title(spec.title.clone()).borders(Borders::ALL);
+    let bars: Vec<Bar> = latest.samples.iter()
+        .filter(|sample| spec.matches(&sample.series))
+        .map(|sample| {
+            let value = sample.value / sample.series.unit.display_scale().0;
+            Bar::default()
+                .label(Line::from(sample.series.name.clone()))
+                // Bars take whole numbers, so they count tenths to keep small values apart
+                .value((value * 10.0).round() as u64)
+                .text_value(format!("{:.0}", value))
+        })
+        .collect();
+    if bars.is_empty() {
+        f.render_widget(block, area);
+        return;
+    }
+
+    // Share the width between the bars, one column apart
+    let width = (block.inner(area).width + 1) / bars.len() as u16;
+    let mut chart = BarChart::default()
+        .block(block)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(width.saturating_sub(1).max(1))
+        .bar_gap(1)
+        .bar_style(fg(app, Color::Cyan))
+        .value_style(fg(app, Color::Cyan).add_modifier(Modifier::REVERSED))
+        .label_style(fg(app, Color::Gray));
+    if let Some((_, max)) = spec.y_bounds {
+        chart = chart.max((max * 10.0).round() as u64);
+    }
+    f.render_widget(chart, area);
+}
+
+/// Points of a series in display units, and its Y-axis bounds.
+type LineData = (Vec<(f64, f64)>, (f64, f64));
+
+/// Turns each series into the running total of the ones before it, and
+/// returns the band between consecutive totals as vertical strokes, since
+/// the chart widget has no filled area. Totals are kept per point in time, so
+/// a series missing from some intervals adds nothing to the ones above there.
+fn stack(series_data: &mut [LineData]) -> Vec<Vec<(f64, f64)>> {
+    let mut totals: HashMap<u64, f64> = HashMap::new();
+    let mut areas = Vec::new();
+    for (data, _) in series_data.iter_mut() {
+        let mut area = Vec::new();
+        for point in data.iter_mut() {
+            let total = totals.entry(point.0.to_bits()).or_insert(0.0);
+            area.push((point.0, *total));
+            *total += point.1;
+            point.1 = *total;
+            area.push(*point);
+        }
+        areas.push(area);
+    }
+
+    let top = totals.values().copied().fold(0.0, f64::max);
+    for (_, bounds) in series_data.iter_mut() {
+        *bounds = (0.0, top * 1.05);
+    }
+    areas
+}
+
+/// Formats a number of seconds for axis labels, e.g. `90s`, `2m` or `1.5h`.
+fn format_span(seconds: f64) -> String {
+    let (value, suffix) = if seconds >= 3600.0 {
+        (seconds / 3600.0, "h")
+    } else if seconds >= 60.0 {
+        (seconds / 60.0, "m")
+    } else {
+        (seconds, "s")
+    };
+    // Round to one decimal and drop it when it is zero
+    format!("{}{}", (value * 10.0).round() / 10.0, suffix)
+}
+
+/// Formats a replay position as `mm:ss`, or `h:mm:ss` past an hour.
+fn format_clock(time: TimeDelta) -> String {
+    let seconds = time.num_seconds().max(0);
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}